@@ -0,0 +1,138 @@
+//! Compares the fast paths of the crate with the straightforward ways of doing the same
+//!
+//! Run with `cargo bench`. This doesn't use the unstable bench harness, so it
+//! works on stable and just prints the average time of each run.
+extern crate utf16_ext;
+
+use std::hint::black_box;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use utf16_ext::*;
+use utf16_ext::byteorder::{LE, BE, WriteBytesExt};
+
+/// Runs `f` until it has taken long enough to time, then prints the time per run
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    f();
+    let mut runs = 1u32;
+    loop {
+        let start = Instant::now();
+        for _ in 0..runs {
+            f();
+        }
+        let elapsed = start.elapsed();
+        if elapsed >= Duration::from_millis(200) {
+            println!("{:<44} {:>12.0} ns/run", name, elapsed.as_nanos() as f64 / f64::from(runs));
+            return
+        }
+        runs *= 2;
+    }
+}
+
+/// About 64k chars of mostly ASCII text with some other chars mixed in
+fn mixed_text() -> String {
+    "Hello, world! Grüße, ünïcödé and 😀 emoji.\n".repeat(1500)
+}
+
+fn le_bytes(s: &str) -> Vec<u8> {
+    let mut v = Vec::new();
+    v.write_utf16_string::<LE>(s).unwrap();
+    v
+}
+
+fn decode_slice_vs_cursor() {
+    let bytes = le_bytes(&mixed_text());
+    bench("decode: utf16_chars over a Cursor", || {
+        let chars = Cursor::new(black_box(&bytes[..])).utf16_chars::<LE>();
+        let n = chars.filter(|c| c.is_ok()).count();
+        black_box(n);
+    });
+    bench("decode: decode_slice", || {
+        let n = decode_slice::<LE>(black_box(&bytes[..])).filter(|c| c.is_ok()).count();
+        black_box(n);
+    });
+}
+
+/// About 64k chars of ASCII text
+fn ascii_text() -> String {
+    "Hello, world! Nothing but plain ASCII here.\n".repeat(1500)
+}
+
+fn read_to_string_vs_per_char() {
+    for &(kind, ref text) in &[("ASCII", ascii_text()), ("mixed", mixed_text())] {
+        let bytes = le_bytes(text);
+        let mut s = String::with_capacity(2 * text.len());
+        bench(&format!("to string {}: push each char", kind), || {
+            s.clear();
+            for c in decode_slice::<LE>(black_box(&bytes[..])) {
+                s.push(c.unwrap());
+            }
+            black_box(&s);
+        });
+        bench(&format!("to string {}: read_to_string", kind), || {
+            s.clear();
+            decode_slice::<LE>(black_box(&bytes[..])).read_to_string(&mut s).unwrap();
+            black_box(&s);
+        });
+    }
+}
+
+fn mixed_units() -> Vec<u16> {
+    mixed_text().encode_utf16().collect()
+}
+
+fn write_all_shorts_vs_per_unit() {
+    let units = mixed_units();
+    let mut out = Vec::with_capacity(2 * units.len());
+    bench("write BE: write_u16 per unit", || {
+        out.clear();
+        for &u in black_box(&units) {
+            out.write_u16::<BE>(u).unwrap();
+        }
+        black_box(&out);
+    });
+    bench("write BE: write_all_shorts", || {
+        out.clear();
+        out.write_all_shorts::<BE>(black_box(&units)).unwrap();
+        black_box(&out);
+    });
+    bench("write BE: write_all_shorts one char at a time", || {
+        out.clear();
+        for pair in black_box(&units).chunks(2) {
+            out.write_all_shorts::<BE>(pair).unwrap();
+        }
+        black_box(&out);
+    });
+    bench("write BE: write_shorts_buffered", || {
+        out.clear();
+        out.write_shorts_buffered::<BE>(black_box(&units)).unwrap();
+        black_box(&out);
+    });
+    bench("write LE: write_all_shorts", || {
+        out.clear();
+        out.write_all_shorts::<LE>(black_box(&units)).unwrap();
+        black_box(&out);
+    });
+}
+
+fn append_to_vec_vs_write_shorts() {
+    let units = mixed_units();
+    let mut out = Vec::with_capacity(2 * units.len());
+    bench("vec BE: write_shorts", || {
+        out.clear();
+        out.write_shorts::<BE>(black_box(&units)).unwrap();
+        black_box(&out);
+    });
+    bench("vec BE: append_utf16_to_vec", || {
+        out.clear();
+        append_utf16_to_vec::<BE>(&mut out, black_box(&units));
+        black_box(&out);
+    });
+}
+
+fn main() {
+    decode_slice_vs_cursor();
+    read_to_string_vs_per_char();
+    write_all_shorts_vs_per_unit();
+    append_to_vec_vs_write_shorts();
+}
@@ -1,16 +1,104 @@
 use *;
 use byteorder::{LE, BE};
 
-use std::io::{Error, ErrorKind};
+use prefixed::read_up_to;
 
+use std::io::{Read, BufRead, Seek, SeekFrom, Cursor, Error, ErrorKind};
+
+pub(crate) const UTF8_BOM: [u8; 3] = [0xef, 0xbb, 0xbf];
+
+pub(crate) fn utf8_bom() -> Error {
+    Error::new(ErrorKind::InvalidData,
+               "Stream starts with a utf-8 bom, so it looks like utf-8 rather than utf-16")
+}
+
+pub(crate) fn no_bom() -> Error {
+    Error::new(ErrorKind::InvalidData, "First character wasn't a bom")
+}
+
+/// Reads the first short to look for a bom
+///
+/// If there isn't one, the bytes that were read are returned along with how many there are,
+/// so they can be put back. If they look like the start of a utf-8 bom, the third byte
+/// is read as well so it can be checked against `UTF8_BOM`.
+pub(crate) fn sniff_bom<R: Read + ?Sized>(r: &mut R)
+-> Result<(Option<Endianness>, [u8; 3], usize), Error> {
+    let mut bom = [0; 3];
+    let mut n = read_up_to(r, &mut bom[..2])?;
+    if n == 2 {
+        if let Some(e) = Endianness::from_bom_bytes([bom[0], bom[1]]) {
+            return Ok((Some(e), bom, 0))
+        }
+        if bom[..2] == UTF8_BOM[..2] {
+            n += read_up_to(r, &mut bom[2..])?;
+        }
+    }
+    Ok((None, bom, n))
+}
+
+/// Reads the first short of `reader` to check if it's a bom
+///
+/// If it is, the bom is consumed and its endianness is returned. Otherwise `None` is
+/// returned and the bytes that were read are put back in front of the returned reader,
+/// so nothing is lost. Unlike `AutoEndianReader::new_auto_bom`, this doesn't check for a utf-8 bom.
+pub fn scan_bom<R: Read>(mut reader: R) -> Result<(Option<Endianness>, PrefixedReader<R>), Error> {
+    let mut bom = [0; 2];
+    let n = read_up_to(&mut reader, &mut bom)?;
+    Ok(match Endianness::from_bom_bytes(bom) {
+        Some(e) if n == 2 => (Some(e), PrefixedReader::new(&[], reader)),
+        _ => (None, PrefixedReader::new(&bom[..n], reader)),
+    })
+}
+
+/// Looks at the first short of `reader` to check if it's a bom without consuming anything
+///
+/// Returns the endianness of the bom, or `None` if there isn't one, leaving `reader`
+/// as it was, bom included. Since only what `fill_buf` returns can be looked at, it can't
+/// be told if there is a bom when the buffer of `reader` holds only a single byte, so an
+/// error with `ErrorKind::UnexpectedEof` is returned then.
+pub fn peek_endianness<R: BufRead + ?Sized>(reader: &mut R) -> Result<Option<Endianness>, Error> {
+    let buf = loop {
+        match reader.fill_buf() {
+            Ok(buf) => break buf,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    };
+    match *buf {
+        [a, b, ..] => Ok(Endianness::from_bom_bytes([a, b])),
+        [_] => {
+            let msg = "only one byte is buffered, so the bom can't be peeked at";
+            Err(Error::new(ErrorKind::UnexpectedEof, msg))
+        }
+        [] => Ok(None),
+    }
+}
+
+/// Decodes a whole byte slice of utf-16 that starts with a bom into a `String`
+///
+/// Returns the text, without the bom, along with the endianness the bom was in.
+/// A missing bom is an error like in `AutoEndianReader::new_auto_bom`.
+pub fn decode_utf16_auto(bytes: &[u8]) -> Result<(String, Endianness), Error> {
+    let mut reader = AutoEndianReader::new_auto_bom(Cursor::new(bytes))?;
+    let mut buf = String::with_capacity(bytes.len() / 2);
+    reader.read_utf16_to_string(&mut buf)?;
+    Ok((buf, reader.endianness()))
+}
+
+#[derive(Debug, Clone)]
 /// A writer that will store whether to write in little or big endian
-pub enum AutoWriter<W> {
+pub enum AutoEndianWriter<W> {
     /// Little endian writer
     Little(W),
     /// Big endian writer
     Big(W)
 }
 
+/// The old name of `AutoEndianWriter`
+#[deprecated(note = "renamed to `AutoEndianWriter`")]
+pub type AutoWriter<W> = AutoEndianWriter<W>;
+
+#[derive(Debug, Clone)]
 /// A reader that will store whether to read in little or big endian
 pub enum AutoEndianReader<R> {
     /// Little endian reader
@@ -19,6 +107,7 @@ pub enum AutoEndianReader<R> {
     Big(R)
 }
 
+#[derive(Debug, Clone)]
 /// An iterator over `char`s from an `AutoEndianReader`
 pub enum AutoEndianChars<R> {
     /// Little endian reader
@@ -27,6 +116,7 @@ pub enum AutoEndianChars<R> {
     Big(Chars<BE, R>)
 }
 
+#[derive(Debug, Clone)]
 /// An iterator over `u16`s from an `AutoEndianReader`
 pub enum AutoEndianShorts<R> {
     /// Little endian reader
@@ -35,6 +125,7 @@ pub enum AutoEndianShorts<R> {
     Big(Shorts<BE, R>)
 }
 
+#[derive(Debug, Clone)]
 /// An iterator over lines from an `AutoEndianReader`
 pub enum AutoEndianLines<R> {
     /// Little endian reader
@@ -44,6 +135,13 @@ pub enum AutoEndianLines<R> {
 }
 
 impl<R> AutoEndianReader<R> {
+    /// Makes a new `AutoEndianReader` in the given endianness
+    pub fn new(inner: R, endianness: Endianness) -> Self {
+        match endianness {
+            Endianness::Little => AutoEndianReader::Little(inner),
+            Endianness::Big => AutoEndianReader::Big(inner),
+        }
+    }
     /// Makes a new `AutoEndianReader` in little endian
     pub fn new_little(inner: R) -> Self {
         AutoEndianReader::Little(inner)
@@ -53,6 +151,7 @@ impl<R> AutoEndianReader<R> {
         AutoEndianReader::Big(inner)
     }
     /// Returns true if this reader is little endian
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_little(&self) -> bool {
         match *self {
             AutoEndianReader::Little(_) => true,
@@ -60,24 +159,77 @@ impl<R> AutoEndianReader<R> {
         }
     }
     /// Returns true if this reader is big endian
+    #[allow(clippy::match_like_matches_macro)]
     pub fn is_big(&self) -> bool {
         match *self {
             AutoEndianReader::Big(_) => true,
             _ => false,
         }
     }
+    /// Returns the endianness of this reader
+    pub fn endianness(&self) -> Endianness {
+        match *self {
+            AutoEndianReader::Little(_) => Endianness::Little,
+            AutoEndianReader::Big(_) => Endianness::Big,
+        }
+    }
+    /// Replaces the underlying reader with what `f` returns for it, keeping the endianness
+    pub fn map_inner<R2, F: FnOnce(R) -> R2>(self, f: F) -> AutoEndianReader<R2> {
+        match self {
+            AutoEndianReader::Little(r) => AutoEndianReader::Little(f(r)),
+            AutoEndianReader::Big(r) => AutoEndianReader::Big(f(r)),
+        }
+    }
+    /// Writes a BOM in `writer` and returns an `AutoEndianWriter` in the same endianness
+    /// as this reader
+    ///
+    /// This is the same as `AutoEndianWriter::mirroring`.
+    pub fn matching_writer<W: Utf16WriteExt>(&self, writer: W)
+    -> Result<AutoEndianWriter<W>, Error> {
+        AutoEndianWriter::mirroring(self, writer)
+    }
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        match *self {
+            AutoEndianReader::Little(ref r) | AutoEndianReader::Big(ref r) => r,
+        }
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        match *self {
+            AutoEndianReader::Little(ref mut r) | AutoEndianReader::Big(ref mut r) => r,
+        }
+    }
+    /// Unwraps this reader, returning the underlying reader
+    ///
+    /// Use `endianness` first to find out which endianness was used.
+    pub fn into_inner(self) -> R {
+        match self {
+            AutoEndianReader::Little(r) | AutoEndianReader::Big(r) => r,
+        }
+    }
 }
 
 impl<R: Utf16ReadExt> AutoEndianReader<R> {
     /// Reads a `u16` to detect the endianness
     ///
-    /// If the value isn't a valid bom (U+FEFF), an error is thrown
+    /// If the value isn't a valid bom (U+FEFF), an error is thrown.
+    /// If the stream starts with a utf-8 bom instead, the error says so.
+    /// The detected endianness can be found with `endianness`.
     pub fn new_auto_bom(mut inner: R) -> Result<Self, Error> {
-        let bom = inner.read_u16::<LE>()?;
-        match bom {
-            0xfeff => Ok(AutoEndianReader::Little(inner)),
-            0xfffe => Ok(AutoEndianReader::Big(inner)),
-            _ => Err(Error::new(ErrorKind::InvalidData, "First character wasn't a bom"))
+        let mut bom = [0; 2];
+        inner.read_exact(&mut bom)?;
+        match Endianness::from_bom_bytes(bom) {
+            Some(Endianness::Little) => Ok(AutoEndianReader::Little(inner)),
+            Some(Endianness::Big) => Ok(AutoEndianReader::Big(inner)),
+            None => {
+                let mut third = [0];
+                if bom == UTF8_BOM[..2] && read_up_to(&mut inner, &mut third)? == 1
+                    && third[0] == UTF8_BOM[2] {
+                    return Err(utf8_bom())
+                }
+                Err(no_bom())
+            }
         }
     }
     /// Mirror of `Utf16ReadExt::read_u16` without the type parameter for endianness
@@ -110,6 +262,30 @@ impl<R: Utf16ReadExt> AutoEndianReader<R> {
             AutoEndianReader::Big(ref mut r) => r.read_utf16_line::<BE>(buf),
         }
     }
+    /// Like `read_utf16_line` but a BOM (U+FEFF) at the start of the line is dropped
+    ///
+    /// This is meant for reading the first line of a reader made with `new` over
+    /// a stream that may start with a BOM. Use `read_utf16_line` for the lines after it,
+    /// since U+FEFF at the start of those is part of the text. The returned count doesn't
+    /// include a dropped BOM. Like `read_utf16_line`, the line is appended to `buf`,
+    /// so clearing it between lines reuses its capacity.
+    pub fn read_utf16_line_trim_bom(&mut self, buf: &mut String) -> Result<usize, Error> {
+        let start = buf.len();
+        let len = self.read_utf16_line(buf)?;
+        if buf[start..].starts_with('\u{feff}') {
+            buf.drain(start..start + '\u{feff}'.len_utf8());
+            Ok(len - 1)
+        } else {
+            Ok(len)
+        }
+    }
+    /// Mirror of `Utf16ReadExt::read_utf16_to_string` without the type parameter for endianness
+    pub fn read_utf16_to_string(&mut self, buf: &mut String) -> Result<usize, Error> {
+        match *self {
+            AutoEndianReader::Little(ref mut r) => r.read_utf16_to_string::<LE>(buf),
+            AutoEndianReader::Big(ref mut r) => r.read_utf16_to_string::<BE>(buf),
+        }
+    }
     /// Mirror of `Utf16ReadExt::utf16_lines` without the type parameter for endianness
     pub fn utf16_lines(self) -> AutoEndianLines<R>
     where Self: Sized {
@@ -120,6 +296,50 @@ impl<R: Utf16ReadExt> AutoEndianReader<R> {
     }
 }
 
+/// Reads raw bytes from the underlying reader
+///
+/// The bytes are passed through as they are, without regard for the endianness.
+impl<R: Read> Read for AutoEndianReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        self.get_mut().read(buf)
+    }
+}
+
+/// Seeks the underlying reader
+///
+/// Seeking to an odd byte offset makes every following short straddle two shorts
+/// of the stream, see `seek_shorts` to seek in whole shorts.
+impl<R: Seek> Seek for AutoEndianReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.get_mut().seek(pos)
+    }
+}
+
+impl<R: Seek> AutoEndianReader<R> {
+    /// Mirror of `Utf16ReadExt::seek_shorts`
+    pub fn seek_shorts(&mut self, n: i64) -> Result<u64, Error> {
+        self.seek(SeekFrom::Current(2 * n))
+    }
+}
+
+impl<R: Read> AutoEndianReader<PrefixedReader<R>> {
+    /// Reads a `u16` to detect the endianness, falling back to `default` if it isn't a bom
+    ///
+    /// If the value isn't a valid bom (U+FEFF), the bytes that were read are kept
+    /// and will be read again as data. A bom is dropped just like in `new_auto_bom`.
+    /// A utf-8 bom is still an error, like in `new_auto_bom`.
+    pub fn new_auto_bom_or(mut inner: R, default: Endianness) -> Result<Self, Error> {
+        let (found, bom, n) = sniff_bom(&mut inner)?;
+        if let Some(e) = found {
+            return Ok(AutoEndianReader::new(PrefixedReader::new(&[], inner), e))
+        }
+        if bom[..n] == UTF8_BOM {
+            return Err(utf8_bom())
+        }
+        Ok(AutoEndianReader::new(PrefixedReader::new(&bom[..n], inner), default))
+    }
+}
+
 impl<R: Utf16ReadExt> Iterator for AutoEndianChars<R> {
     type Item = Result<char, Error>;
     fn next(&mut self) -> Option<Self::Item> {
@@ -128,6 +348,22 @@ impl<R: Utf16ReadExt> Iterator for AutoEndianChars<R> {
             AutoEndianChars::Big(ref mut r) => r.next(),
         }
     }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            AutoEndianChars::Little(ref r) => r.size_hint(),
+            AutoEndianChars::Big(ref r) => r.size_hint(),
+        }
+    }
+}
+
+impl<R: Utf16ReadExt> AutoEndianChars<R> {
+    /// Mirror of `Chars::collect_string`
+    pub fn collect_string(self) -> Result<String, Error> {
+        match self {
+            AutoEndianChars::Little(r) => r.collect_string(),
+            AutoEndianChars::Big(r) => r.collect_string(),
+        }
+    }
 }
 
 impl<R: Utf16ReadExt> Iterator for AutoEndianShorts<R> {
@@ -138,6 +374,12 @@ impl<R: Utf16ReadExt> Iterator for AutoEndianShorts<R> {
             AutoEndianShorts::Big(ref mut r) => r.next(),
         }
     }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            AutoEndianShorts::Little(ref r) => r.size_hint(),
+            AutoEndianShorts::Big(ref r) => r.size_hint(),
+        }
+    }
 }
 
 impl<R: Utf16ReadExt> Iterator for AutoEndianLines<R> {
@@ -148,38 +390,135 @@ impl<R: Utf16ReadExt> Iterator for AutoEndianLines<R> {
             AutoEndianLines::Big(ref mut r) => r.next(),
         }
     }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match *self {
+            AutoEndianLines::Little(ref r) => r.size_hint(),
+            AutoEndianLines::Big(ref r) => r.size_hint(),
+        }
+    }
 }
 
-impl<W: Utf16WriteExt> AutoWriter<W> {
-    /// Writes a BOM in the writer and returns a new `AutoWriter` in little endian
+impl<W: Utf16WriteExt> AutoEndianWriter<W> {
+    /// Writes a BOM in the writer and returns a new `AutoEndianWriter` in little endian
     pub fn new_little(mut writer: W) -> Result<Self, Error> {
         writer.write_bom::<LE>()?;
-        Ok(AutoWriter::Little(writer))
+        Ok(AutoEndianWriter::Little(writer))
     }
-    /// Writes a BOM in the writer and returns a new `AutoWriter` in big endian
+    /// Writes a BOM in the writer and returns a new `AutoEndianWriter` in big endian
     pub fn new_big(mut writer: W) -> Result<Self, Error> {
         writer.write_bom::<BE>()?;
-        Ok(AutoWriter::Big(writer))
+        Ok(AutoEndianWriter::Big(writer))
+    }
+    /// Writes a BOM in the writer and returns a new `AutoEndianWriter` in the given endianness
+    pub fn new(writer: W, endianness: Endianness) -> Result<Self, Error> {
+        match endianness {
+            Endianness::Little => AutoEndianWriter::new_little(writer),
+            Endianness::Big => AutoEndianWriter::new_big(writer),
+        }
+    }
+    /// Writes a BOM in the writer and returns a new `AutoEndianWriter` in the same endianness
+    /// as `reader`
+    ///
+    /// This is useful for writing back a file in the same way it was read.
+    pub fn mirroring<R>(reader: &AutoEndianReader<R>, writer: W) -> Result<Self, Error> {
+        AutoEndianWriter::new(writer, reader.endianness())
+    }
+    /// Returns true if this writer is little endian
+    pub fn is_little(&self) -> bool {
+        matches!(*self, AutoEndianWriter::Little(_))
+    }
+    /// Returns true if this writer is big endian
+    pub fn is_big(&self) -> bool {
+        matches!(*self, AutoEndianWriter::Big(_))
+    }
+    /// Returns the endianness of this writer
+    pub fn endianness(&self) -> Endianness {
+        match *self {
+            AutoEndianWriter::Little(_) => Endianness::Little,
+            AutoEndianWriter::Big(_) => Endianness::Big,
+        }
+    }
+    /// Mirror of `Utf16WriteExt::write_bom` without the type parameter for endianness
+    pub fn write_bom(&mut self) -> Result<(), Error> {
+        match *self {
+            AutoEndianWriter::Little(ref mut w) => w.write_bom::<LE>(),
+            AutoEndianWriter::Big(ref mut w) => w.write_bom::<BE>()
+        }
     }
     /// Mirror of `Utf16WriteExt::write_shorts` without the type parameter for endianness
     pub fn write_shorts(&mut self, buf: &[u16]) -> Result<usize, Error> {
         match *self {
-            AutoWriter::Little(ref mut w) => w.write_shorts::<LE>(buf),
-            AutoWriter::Big(ref mut w) => w.write_shorts::<BE>(buf)
+            AutoEndianWriter::Little(ref mut w) => w.write_shorts::<LE>(buf),
+            AutoEndianWriter::Big(ref mut w) => w.write_shorts::<BE>(buf)
         }
     }
     /// Mirror of `Utf16WriteExt::write_all_shorts` without the type parameter for endianness
     pub fn write_all_shorts(&mut self, buf: &[u16]) -> Result<(), Error> {
         match *self {
-            AutoWriter::Little(ref mut w) => w.write_all_shorts::<LE>(buf),
-            AutoWriter::Big(ref mut w) => w.write_all_shorts::<BE>(buf)
+            AutoEndianWriter::Little(ref mut w) => w.write_all_shorts::<LE>(buf),
+            AutoEndianWriter::Big(ref mut w) => w.write_all_shorts::<BE>(buf)
         }
     }
     /// Mirror of `Utf16WriteExt::write_utf16_string` without the type parameter for endianness
     pub fn write_utf16_string<'a>(&mut self, s: &'a str) -> Result<Utf16Written<'a>, Error> {
         match *self {
-            AutoWriter::Little(ref mut w) => w.write_utf16_string::<LE>(s),
-            AutoWriter::Big(ref mut w) => w.write_utf16_string::<BE>(s)
+            AutoEndianWriter::Little(ref mut w) => w.write_utf16_string::<LE>(s),
+            AutoEndianWriter::Big(ref mut w) => w.write_utf16_string::<BE>(s)
         }
     }
+    /// Mirror of `Utf16WriteExt::write_utf16_with_bom` without the type parameter for endianness
+    pub fn write_utf16_with_bom<'a>(&mut self, s: &'a str) -> Result<Utf16Written<'a>, Error> {
+        match *self {
+            AutoEndianWriter::Little(ref mut w) => w.write_utf16_with_bom::<LE>(s),
+            AutoEndianWriter::Big(ref mut w) => w.write_utf16_with_bom::<BE>(s)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::*;
+
+    #[test]
+    fn size_hint_is_forwarded() {
+        let bytes = le_str("\u{feff}a\nb");
+        let auto = || AutoEndianReader::new_auto_bom(Cursor::new(&bytes)).unwrap();
+        let plain = || Cursor::new(&bytes[2..]);
+        assert_eq!(auto().shorts().size_hint(), plain().shorts::<LE>().size_hint());
+        assert_eq!(auto().utf16_chars().size_hint(), plain().utf16_chars::<LE>().size_hint());
+        assert_eq!(auto().utf16_lines().size_hint(), plain().utf16_lines::<LE>().size_hint());
+    }
+
+    #[test]
+    fn peek_endianness_doesnt_consume_anything() {
+        let mut r = ::std::io::BufReader::new(Cursor::new(le_str("\u{feff}a")));
+        assert_eq!(peek_endianness(&mut r).unwrap(), Some(Endianness::Little));
+        assert_eq!(r.stream_position().unwrap(), 0);
+        assert_eq!(r.utf16_chars::<LE>().collect_string().unwrap(), "\u{feff}a");
+        let mut r = ::std::io::BufReader::new(Cursor::new(le_str("a")));
+        assert_eq!(peek_endianness(&mut r).unwrap(), None);
+        assert_eq!(r.stream_position().unwrap(), 0);
+        assert_eq!(peek_endianness(&mut Cursor::new(&[][..])).unwrap(), None);
+    }
+
+    #[test]
+    fn peek_endianness_cant_tell_from_one_buffered_byte() {
+        let mut r = ::std::io::BufReader::with_capacity(1, Cursor::new(le_str("\u{feff}a")));
+        let e = peek_endianness(&mut r).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(r.stream_position().unwrap(), 0);
+    }
+
+    #[test]
+    fn read_utf16_line_trim_bom_only_drops_a_leading_bom() {
+        let bytes = le_str("\u{feff}a\n\u{feff}b\n");
+        let mut r = AutoEndianReader::new(Cursor::new(bytes), Endianness::Little);
+        let mut buf = String::new();
+        assert_eq!(r.read_utf16_line_trim_bom(&mut buf).unwrap(), 2);
+        assert_eq!(buf, "a\n");
+        buf.clear();
+        assert_eq!(r.read_utf16_line(&mut buf).unwrap(), 3);
+        assert_eq!(buf, "\u{feff}b\n");
+    }
 }
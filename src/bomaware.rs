@@ -0,0 +1,120 @@
+use std::io::{Write, IoSlice, Result};
+
+use endian::Endianness;
+
+#[derive(Debug)]
+/// A writer that writes a bom right before the first bytes written to it
+///
+/// This is useful when the same code either starts a new utf-16 file, which needs a bom,
+/// or appends to an existing one, which already has one. The bom is only written once, and
+/// only when something is actually written, so nothing at all is written if this is dropped
+/// before any bytes were. Empty writes don't count.
+pub struct BomAwareWriter<W> {
+    inner: W,
+    endianness: Endianness,
+    bom_pending: bool,
+}
+
+impl<W: Write> BomAwareWriter<W> {
+    /// Makes a new `BomAwareWriter` that writes a bom in `endianness` before the first
+    /// bytes if `write_bom` is true
+    ///
+    /// Without the bom, this just passes everything through to `inner`.
+    pub fn new(inner: W, endianness: Endianness, write_bom: bool) -> Self {
+        BomAwareWriter {
+            inner,
+            endianness,
+            bom_pending: write_bom,
+        }
+    }
+    /// Returns the endianness of the bom
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+    /// Returns true if the bom still has to be written
+    pub fn bom_pending(&self) -> bool {
+        self.bom_pending
+    }
+    /// Gets a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying writer
+    ///
+    /// Writing to it directly doesn't write the bom first.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+    /// Unwraps this writer, returning the underlying writer
+    ///
+    /// A bom that is still pending isn't written.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+    /// Writes the bom if it is still pending
+    ///
+    /// If that fails, the bom is still pending, so a retry writes it again from the start.
+    fn write_pending_bom(&mut self) -> Result<()> {
+        if self.bom_pending {
+            self.inner.write_all(&self.endianness.to_bom_bytes())?;
+            self.bom_pending = false;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for BomAwareWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return Ok(0)
+        }
+        self.write_pending_bom()?;
+        self.inner.write(buf)
+    }
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> Result<usize> {
+        if bufs.iter().all(|buf| buf.is_empty()) {
+            return Ok(0)
+        }
+        self.write_pending_bom()?;
+        self.inner.write_vectored(bufs)
+    }
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::LE;
+    use test_util::*;
+    use write::Utf16WriteExt;
+
+    #[test]
+    fn bom_is_written_once_before_the_first_write() {
+        let mut w = BomAwareWriter::new(Vec::new(), Endianness::Little, true);
+        assert!(w.bom_pending());
+        w.write_all_utf16_string::<LE>("a").unwrap();
+        w.write_all_utf16_string::<LE>("b").unwrap();
+        assert!(!w.bom_pending());
+        assert_eq!(w.into_inner(), le_str("\u{feff}ab"));
+    }
+
+    #[test]
+    fn bom_is_skipped_if_not_asked_for() {
+        let mut w = BomAwareWriter::new(Vec::new(), Endianness::Little, false);
+        w.write_all_utf16_string::<LE>("ab").unwrap();
+        assert_eq!(w.into_inner(), le_str("ab"));
+    }
+
+    #[test]
+    fn nothing_is_written_without_writes() {
+        let mut out = Vec::new();
+        {
+            let mut w = BomAwareWriter::new(&mut out, Endianness::Little, true);
+            w.write_all_utf16_string::<LE>("").unwrap();
+            w.flush().unwrap();
+        }
+        assert!(out.is_empty());
+    }
+}
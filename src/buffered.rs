@@ -0,0 +1,363 @@
+use std::error;
+use std::fmt;
+use std::io::{Read, Write, Error, ErrorKind};
+use std::marker::PhantomData;
+
+use byteorder::ByteOrder;
+
+use endian::{Endianness, BOM};
+use write::DEFAULT_CHUNK_BYTES;
+
+#[derive(Debug)]
+/// An iterator over `u16` values of a reader that reads the bytes in chunks
+///
+/// This is made with `buffered_shorts` and yields the same items as `Shorts`,
+/// but only calls `read` on the inner reader whenever its buffer runs out.
+pub struct BufShorts<T: ByteOrder, R> {
+    buf: Vec<u8>,
+    pos: usize,
+    end: usize,
+    done: bool,
+    inner: R,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, R> BufShorts<T, R> {
+    /// Makes a new `BufShorts` with a buffer of `cap` bytes
+    ///
+    /// A `cap` lower than 2 is rounded up to 2, so that a short always fits.
+    pub(crate) fn new(inner: R, cap: usize) -> Self {
+        BufShorts {
+            buf: vec![0; cap.max(2)],
+            pos: 0,
+            end: 0,
+            done: false,
+            inner,
+            _order: PhantomData,
+        }
+    }
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    ///
+    /// Reading from it directly skips over the bytes that are still buffered.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Returns the bytes that have been read from the inner reader but not yielded yet
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..self.end]
+    }
+    /// Unwraps this `BufShorts`, returning the underlying reader
+    ///
+    /// Any buffered bytes are lost, so check `buffer` first if they matter.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<T: ByteOrder, R: Read> BufShorts<T, R> {
+    /// Reads until at least a whole short is buffered or the reader ends
+    fn fill(&mut self) -> Result<(), Error> {
+        // Move a lone byte left over from the last fill to the front,
+        // so the short it starts can be completed by the next read
+        if self.pos < self.end {
+            self.buf[0] = self.buf[self.pos];
+        }
+        self.end -= self.pos;
+        self.pos = 0;
+        while self.end < 2 {
+            match self.inner.read(&mut self.buf[self.end..]) {
+                Ok(0) => {
+                    self.done = true;
+                    break
+                }
+                Ok(n) => self.end += n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: ByteOrder, R: Read> Iterator for BufShorts<T, R> {
+    type Item = Result<u16, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.end - self.pos < 2 && !self.done {
+            if let Err(e) = self.fill() {
+                return Some(Err(e))
+            }
+        }
+        match self.end - self.pos {
+            0 => None,
+            1 => {
+                self.pos = self.end;
+                let msg = "stream ended in the middle of a short";
+                Some(Err(Error::new(ErrorKind::UnexpectedEof, msg)))
+            }
+            _ => {
+                let short = T::read_u16(&self.buf[self.pos..self.pos + 2]);
+                self.pos += 2;
+                Some(Ok(short))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A writer that collects utf-16 in a buffer and writes it to the inner writer in bulk
+///
+/// Like `BufWriter`, the buffer is written when it's full, on `flush` and when this
+/// is dropped, in which case errors are ignored. Besides implementing `Write` (and so
+/// `Utf16WriteExt`), it has `put_` methods writing in the endianness it was made with,
+/// which encode straight into the buffer.
+///
+/// Shorts are only ever written as a whole by those methods, but the two halves of
+/// a surrogate pair can end up in different writes to the inner writer. That makes no
+/// difference to the bytes that come out.
+pub struct BufUtf16Writer<W: Write> {
+    // Only `None` after `into_inner`, so that `Drop` doesn't flush
+    inner: Option<W>,
+    buf: Vec<u8>,
+    cap: usize,
+    endianness: Endianness,
+}
+
+impl<W: Write> BufUtf16Writer<W> {
+    /// Makes a new `BufUtf16Writer` with a buffer of `DEFAULT_CHUNK_BYTES` bytes
+    pub fn new(inner: W, endianness: Endianness) -> Self {
+        BufUtf16Writer::with_capacity(DEFAULT_CHUNK_BYTES, inner, endianness)
+    }
+    /// Makes a new `BufUtf16Writer` with a buffer of `cap` bytes
+    ///
+    /// A `cap` lower than 2 is rounded up to 2, so that a short always fits.
+    pub fn with_capacity(cap: usize, inner: W, endianness: Endianness) -> Self {
+        let cap = cap.max(2);
+        BufUtf16Writer {
+            inner: Some(inner),
+            buf: Vec::with_capacity(cap),
+            cap,
+            endianness,
+        }
+    }
+    /// Returns the endianness this writer encodes in
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+    /// Returns the bytes that haven't been written to the inner writer yet
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf
+    }
+    /// Gets a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+    /// Gets a mutable reference to the underlying writer
+    ///
+    /// Writing to it directly skips ahead of the bytes that are still buffered.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().unwrap()
+    }
+    /// Writes the buffer and unwraps this writer, returning the underlying writer
+    ///
+    /// If the buffer can't be written, the error is returned along with this writer,
+    /// so neither the rest of the buffer nor the underlying writer is lost.
+    pub fn into_inner(mut self) -> Result<W, IntoInnerError<W>> {
+        match self.flush_buf() {
+            Ok(()) => Ok(self.inner.take().unwrap()),
+            Err(e) => Err(IntoInnerError(self, e)),
+        }
+    }
+    /// Writes a short in the endianness of this writer
+    pub fn put_short(&mut self, short: u16) -> Result<(), Error> {
+        if self.buf.len() + 2 > self.cap {
+            self.flush_buf()?;
+        }
+        let bytes = match self.endianness {
+            Endianness::Little => short.to_le_bytes(),
+            Endianness::Big => short.to_be_bytes(),
+        };
+        self.buf.extend_from_slice(&bytes);
+        Ok(())
+    }
+    /// Writes all of `buf` in the endianness of this writer
+    pub fn put_shorts(&mut self, buf: &[u16]) -> Result<(), Error> {
+        for &short in buf {
+            self.put_short(short)?;
+        }
+        Ok(())
+    }
+    /// Writes a byte order mark in the endianness of this writer
+    pub fn put_bom(&mut self) -> Result<(), Error> {
+        self.put_short(BOM)
+    }
+    /// Writes a string as utf-16 in the endianness of this writer
+    pub fn put_str(&mut self, s: &str) -> Result<(), Error> {
+        for short in s.encode_utf16() {
+            self.put_short(short)?;
+        }
+        Ok(())
+    }
+    /// Writes the buffer to the inner writer
+    ///
+    /// Whatever was written is removed from the buffer, also when an error happens.
+    fn flush_buf(&mut self) -> Result<(), Error> {
+        let inner = self.inner.as_mut().unwrap();
+        let mut written = 0;
+        let mut ret = Ok(());
+        while written < self.buf.len() {
+            match inner.write(&self.buf[written..]) {
+                Ok(0) => {
+                    let e = Error::new(ErrorKind::WriteZero, "failed to write the buffered data");
+                    ret = Err(e);
+                    break
+                }
+                Ok(n) => written += n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => {
+                    ret = Err(e);
+                    break
+                }
+            }
+        }
+        self.buf.drain(..written);
+        ret
+    }
+}
+
+impl<W: Write> Write for BufUtf16Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
+        if self.buf.len() + buf.len() > self.cap {
+            self.flush_buf()?;
+        }
+        if buf.len() >= self.cap {
+            self.get_mut().write(buf)
+        } else {
+            self.buf.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+    fn flush(&mut self) -> Result<(), Error> {
+        self.flush_buf()?;
+        self.get_mut().flush()
+    }
+}
+
+impl<W: Write> Drop for BufUtf16Writer<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.flush_buf();
+        }
+    }
+}
+
+#[derive(Debug)]
+/// The error of `BufUtf16Writer::into_inner` when the buffer couldn't be written
+///
+/// Like `std::io::IntoInnerError`, this holds on to the writer, which still has
+/// the part of the buffer that wasn't written.
+pub struct IntoInnerError<W: Write>(BufUtf16Writer<W>, Error);
+
+impl<W: Write> IntoInnerError<W> {
+    /// Returns the error that happened while writing the buffer
+    pub fn error(&self) -> &Error {
+        &self.1
+    }
+    /// Returns the writer, so the buffer can be written again or recovered
+    pub fn into_inner(self) -> BufUtf16Writer<W> {
+        self.0
+    }
+    /// Returns the error, dropping the writer
+    ///
+    /// Dropping the writer tries to write the buffer once more.
+    pub fn into_error(self) -> Error {
+        self.1
+    }
+}
+
+impl<W: Write> fmt::Display for IntoInnerError<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.1.fmt(f)
+    }
+}
+
+impl<W: Write + fmt::Debug> error::Error for IntoInnerError<W> {}
+
+impl<W: Write> From<IntoInnerError<W>> for Error {
+    fn from(e: IntoInnerError<W>) -> Error {
+        e.into_error()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::LE;
+    use read::Utf16ReadExt;
+    use test_util::*;
+    use write::Utf16WriteExt;
+
+    #[test]
+    fn extension_methods_arent_shadowed() {
+        let mut w = BufUtf16Writer::new(Vec::new(), Endianness::Little);
+        w.put_str("a").unwrap();
+        assert!(w.write_utf16_string::<LE>("b").unwrap().is_complete());
+        w.write_all_shorts::<LE>(&[0x63]).unwrap();
+        assert_eq!(w.into_inner().unwrap(), le_str("abc"));
+    }
+
+    #[test]
+    fn into_inner_keeps_the_writer_on_error() {
+        let inner = MockWriter::new().capacity(2, Full::Error);
+        let mut w = BufUtf16Writer::new(inner, Endianness::Little);
+        w.put_str("ab").unwrap();
+        let e = w.into_inner().unwrap_err();
+        assert_eq!(e.error().kind(), ErrorKind::Other);
+        let w = e.into_inner();
+        assert_eq!(w.buffer(), &le_str("b")[..]);
+        assert_eq!(w.get_ref().written, le_str("a"));
+    }
+
+    const UNITS: [u16; 5] = [0x61, 0xd83d, 0xde00, 0x62, 0x63];
+
+    #[test]
+    fn buffered_shorts_put_split_shorts_back_together() {
+        let bytes = le(&UNITS);
+        for cap in 1..4 {
+            // Reads of 3 bytes end in the middle of every other short
+            let steps = bytes.chunks(3).map(|c| Ok(c.to_vec())).collect();
+            let shorts = ScriptedReader::new(steps).buffered_shorts::<LE>(cap);
+            assert_eq!(shorts.map(Result::unwrap).collect::<Vec<_>>(), UNITS);
+            let shorts = ScriptedReader::one_byte(&bytes).buffered_shorts::<LE>(cap);
+            assert_eq!(shorts.map(Result::unwrap).collect::<Vec<_>>(), UNITS);
+        }
+    }
+
+    #[test]
+    fn buffered_shorts_odd_trailing_byte() {
+        let mut bytes = le(&UNITS);
+        bytes.push(0x7a);
+        for cap in 1..4 {
+            let steps = bytes.chunks(3).map(|c| Ok(c.to_vec())).collect();
+            let mut shorts = ScriptedReader::new(steps).buffered_shorts::<LE>(cap);
+            for &unit in &UNITS {
+                assert_eq!(shorts.next().unwrap().unwrap(), unit);
+            }
+            let e = shorts.next().unwrap().unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+            assert!(shorts.next().is_none());
+        }
+    }
+
+    #[test]
+    fn buffered_shorts_keep_a_half_short_across_an_error() {
+        let steps = vec![Ok(vec![0x61]), Err(Error::other("failed")), Ok(vec![0, 0x62, 0])];
+        let mut shorts = ScriptedReader::new(steps).buffered_shorts::<LE>(3);
+        assert_eq!(shorts.next().unwrap().unwrap_err().kind(), ErrorKind::Other);
+        assert_eq!(shorts.buffer(), [0x61]);
+        assert_eq!(shorts.map(Result::unwrap).collect::<Vec<_>>(), [0x61, 0x62]);
+    }
+}
@@ -0,0 +1,261 @@
+use std::char::REPLACEMENT_CHARACTER;
+use std::io::{Read, Error};
+
+use byteorder::{LE, BE};
+
+use auto::AutoEndianReader;
+use endian::Endianness;
+use lines::{LineOptions, ConfiguredLines};
+use prefixed::PrefixedReader;
+use read::{TrimMode, DecodeState, decode_char};
+
+#[derive(Debug)]
+/// A builder for reading utf-16 from a reader with options chosen at runtime
+///
+/// This owns the reader and collects the options that would otherwise be spread over
+/// different methods of `Utf16ReadExt` and `AutoEndianReader`, then makes a reader,
+/// an iterator over `char`s or an iterator over lines from them, e.g.
+/// `Utf16ReaderBuilder::new(r).skip_bom(true).lossy(true).lines()`.
+pub struct Utf16ReaderBuilder<R> {
+    inner: R,
+    endianness: Endianness,
+    skip_bom: bool,
+    lossy: bool,
+    lines: LineOptions,
+}
+
+impl<R: Read> Utf16ReaderBuilder<R> {
+    /// Makes a new builder reading little endian utf-16 from `inner`
+    pub fn new(inner: R) -> Self {
+        Utf16ReaderBuilder {
+            inner,
+            endianness: Endianness::Little,
+            skip_bom: false,
+            lossy: false,
+            lines: LineOptions::default(),
+        }
+    }
+    /// Sets the endianness to read in (little endian by default)
+    ///
+    /// If `skip_bom` is set, this is only used when the stream doesn't start with a bom.
+    pub fn endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+    /// Sets whether a bom at the start of the stream should be dropped (off by default)
+    ///
+    /// A bom in either endianness is dropped and decides the endianness, like in
+    /// `AutoEndianReader::new_auto_bom_or`. Without it, a bom is read as U+FEFF.
+    pub fn skip_bom(mut self, skip_bom: bool) -> Self {
+        self.skip_bom = skip_bom;
+        self
+    }
+    /// Sets whether malformed utf-16 should be replaced with U+FFFD instead of
+    /// being yielded as an error (off by default)
+    ///
+    /// This applies to `chars` and `lines`.
+    pub fn lossy(mut self, lossy: bool) -> Self {
+        self.lossy = lossy;
+        self
+    }
+    /// Sets the char that ends a line for `lines`, see `LinesBuilder::delimiter`
+    pub fn line_delimiter(mut self, delimiter: char) -> Self {
+        self.lines.delimiter = delimiter;
+        self
+    }
+    /// Sets the maximum length of a line for `lines`, see `LinesBuilder::max_len`
+    pub fn max_line_len(mut self, max_len: usize) -> Self {
+        self.lines.max_len = Some(max_len);
+        self
+    }
+    /// Sets how whitespace is trimmed from the lines of `lines`, see `LinesBuilder::trim`
+    pub fn trim_lines(mut self, mode: TrimMode) -> Self {
+        self.lines.trim = mode;
+        self
+    }
+    /// Sets whether `lines` keeps line endings, see `LinesBuilder::keep_ends`
+    pub fn keep_line_ends(mut self, keep_ends: bool) -> Self {
+        self.lines.keep_ends = keep_ends;
+        self
+    }
+    /// Makes the reader, reading the bom if `skip_bom` is set
+    ///
+    /// A stream starting with a utf-8 bom gives an error with `ErrorKind::InvalidData`
+    /// if `skip_bom` is set. The options for chars and lines don't apply to the reader.
+    pub fn build(self) -> Result<AutoEndianReader<PrefixedReader<R>>, Error> {
+        if self.skip_bom {
+            AutoEndianReader::new_auto_bom_or(self.inner, self.endianness)
+        } else {
+            Ok(AutoEndianReader::new(PrefixedReader::new(&[], self.inner), self.endianness))
+        }
+    }
+    /// Makes an iterator over the `char`s of the reader
+    ///
+    /// Errors from reading the bom are returned like in `build`.
+    pub fn chars(self) -> Result<BuiltChars<R>, Error> {
+        let lossy = self.lossy;
+        let (inner, endianness) = into_parts(self.build()?);
+        Ok(BuiltChars { inner, endianness, lossy, state: DecodeState::default() })
+    }
+    /// Makes an iterator over the lines of the reader
+    ///
+    /// Without setting any line options, this behaves like `Utf16ReadExt::utf16_lines`.
+    /// Errors from reading the bom are returned like in `build`.
+    pub fn lines(self) -> Result<BuiltLines<R>, Error> {
+        let mut options = self.lines;
+        options.lossy = self.lossy;
+        Ok(match self.build()? {
+            AutoEndianReader::Little(r) => BuiltLines::Little(ConfiguredLines::new(r, options)),
+            AutoEndianReader::Big(r) => BuiltLines::Big(ConfiguredLines::new(r, options)),
+        })
+    }
+}
+
+fn into_parts<R>(r: AutoEndianReader<R>) -> (R, Endianness) {
+    match r {
+        AutoEndianReader::Little(r) => (r, Endianness::Little),
+        AutoEndianReader::Big(r) => (r, Endianness::Big),
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over the `char`s of a reader configured by a `Utf16ReaderBuilder`
+pub struct BuiltChars<R> {
+    inner: PrefixedReader<R>,
+    endianness: Endianness,
+    lossy: bool,
+    state: DecodeState,
+}
+
+impl<R> BuiltChars<R> {
+    /// Returns the endianness the chars are decoded in
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    ///
+    /// Bytes that were read while looking for a bom but not decoded yet are lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<R: Read> Iterator for BuiltChars<R> {
+    type Item = Result<char, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let d = match self.endianness {
+            Endianness::Little => decode_char::<LE, _>(&mut self.inner, &mut self.state)?,
+            Endianness::Big => decode_char::<BE, _>(&mut self.inner, &mut self.state)?,
+        };
+        if self.lossy && d.malformed {
+            Some(Ok(REPLACEMENT_CHARACTER))
+        } else {
+            Some(d.result)
+        }
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over the lines of a reader configured by a `Utf16ReaderBuilder`
+pub enum BuiltLines<R> {
+    /// Little endian reader
+    Little(ConfiguredLines<LE, PrefixedReader<R>>),
+    /// Big endian reader
+    Big(ConfiguredLines<BE, PrefixedReader<R>>),
+}
+
+impl<R: Read> Iterator for BuiltLines<R> {
+    type Item = Result<String, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            BuiltLines::Little(ref mut r) => r.next(),
+            BuiltLines::Big(ref mut r) => r.next(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, ErrorKind};
+
+    use super::*;
+    use endian::BOM;
+    use error::LineTooLong;
+    use test_util::*;
+
+    fn be(units: &[u16]) -> Vec<u8> {
+        units.iter().flat_map(|u| u.to_be_bytes().to_vec()).collect()
+    }
+
+    fn chars<R: Read>(builder: Utf16ReaderBuilder<R>) -> (Endianness, String) {
+        let chars = builder.chars().unwrap();
+        let endianness = chars.endianness();
+        (endianness, chars.map(Result::unwrap).collect())
+    }
+
+    #[test]
+    fn skip_bom_with_each_bom() {
+        let r = Cursor::new(le(&[BOM, 0x61]));
+        let builder = Utf16ReaderBuilder::new(r).endianness(Endianness::Big).skip_bom(true);
+        assert_eq!(chars(builder), (Endianness::Little, "a".to_owned()));
+
+        let r = Cursor::new(be(&[BOM, 0x61]));
+        let builder = Utf16ReaderBuilder::new(r).skip_bom(true);
+        assert_eq!(chars(builder), (Endianness::Big, "a".to_owned()));
+
+        let r = Cursor::new(be(&[0x61]));
+        let builder = Utf16ReaderBuilder::new(r).endianness(Endianness::Big).skip_bom(true);
+        assert_eq!(chars(builder), (Endianness::Big, "a".to_owned()));
+
+        let r = Cursor::new(vec![0xef, 0xbb, 0xbf, b'a', 0]);
+        let e = Utf16ReaderBuilder::new(r).skip_bom(true).chars().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn bom_is_a_char_without_skip_bom() {
+        let builder = Utf16ReaderBuilder::new(Cursor::new(le(&[BOM, 0x61])));
+        assert_eq!(chars(builder), (Endianness::Little, "\u{feff}a".to_owned()));
+    }
+
+    #[test]
+    fn lossy_chars_replace_malformed_utf16() {
+        let bytes = le(&[0x61, 0xdc00, 0xd800, 0x62, 0xd83d, 0xde00, 0xd800]);
+        let builder = Utf16ReaderBuilder::new(Cursor::new(&bytes)).lossy(true);
+        assert_eq!(chars(builder).1, "a\u{fffd}\u{fffd}b\u{1f600}\u{fffd}");
+
+        let chars = Utf16ReaderBuilder::new(Cursor::new(&bytes)).chars().unwrap();
+        let chars = chars.map(|c| c.map_err(|e| e.kind())).collect::<Vec<_>>();
+        assert_eq!(chars[1], Err(ErrorKind::InvalidData));
+        assert_eq!(chars[3], Ok('b'));
+    }
+
+    #[test]
+    fn lines_get_the_line_options() {
+        let r = Cursor::new(le_str(" a ;b\r\n;"));
+        let lines = Utf16ReaderBuilder::new(r)
+            .line_delimiter(';')
+            .trim_lines(TrimMode::Both)
+            .keep_line_ends(true)
+            .lines()
+            .unwrap();
+        assert_eq!(lines.map(Result::unwrap).collect::<Vec<_>>(), ["a;", "b;"]);
+
+        let r = Cursor::new(le(&[0x61, 0xdc00, 0x0a, 0x62, 0x63, 0x64, 0x0a, 0x65]));
+        let mut lines = Utf16ReaderBuilder::new(r).lossy(true).max_line_len(2).lines().unwrap();
+        assert_eq!(lines.next().unwrap().unwrap(), "a\u{fffd}");
+        let e = lines.next().unwrap().unwrap_err();
+        let too_long = e.get_ref().unwrap().downcast_ref::<LineTooLong>().unwrap();
+        assert_eq!(*too_long, LineTooLong::new(2));
+        assert_eq!(lines.next().unwrap().unwrap(), "e");
+        assert!(lines.next().is_none());
+    }
+}
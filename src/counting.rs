@@ -0,0 +1,108 @@
+use std::io::{Read, Write, IoSlice, Result};
+
+#[derive(Debug)]
+/// A reader that counts how many bytes have been read from the inner reader
+///
+/// Wrapping a reader in this before calling `utf16_chars` or similar makes it possible
+/// to find where in the stream an error happened. Since the count is taken at the byte
+/// level, it stays accurate when a `char` is decoded from a surrogate pair.
+/// Note that an error for an invalid surrogate pair is yielded after both of its
+/// shorts have been read, so the count then points just past the pair.
+pub struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R> CountingReader<R> {
+    /// Makes a new `CountingReader` that has read nothing yet
+    pub fn new(inner: R) -> Self {
+        CountingReader {
+            inner,
+            count: 0,
+        }
+    }
+    /// Returns the number of bytes read so far
+    pub fn bytes_read(&self) -> u64 {
+        self.count
+    }
+    /// Returns the number of whole shorts read so far
+    pub fn shorts_read(&self) -> u64 {
+        self.count / 2
+    }
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    ///
+    /// Bytes read directly from it aren't counted.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Unwraps this reader, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+}
+
+#[derive(Debug)]
+/// A writer that counts how many bytes have been written to the inner writer
+///
+/// Only bytes the inner writer accepted are counted, so after a failed write the count
+/// is still accurate. This is useful for writing a length prefix afterwards.
+pub struct CountingWriter<W> {
+    inner: W,
+    count: u64,
+}
+
+impl<W> CountingWriter<W> {
+    /// Makes a new `CountingWriter` that has written nothing yet
+    pub fn new(inner: W) -> Self {
+        CountingWriter {
+            inner,
+            count: 0,
+        }
+    }
+    /// Returns the number of bytes written so far
+    pub fn bytes_written(&self) -> u64 {
+        self.count
+    }
+    /// Gets a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying writer
+    ///
+    /// Bytes written directly to it aren't counted.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+    /// Unwraps this writer, returning the underlying writer and the number of bytes written
+    pub fn into_inner(self) -> (W, u64) {
+        (self.inner, self.count)
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> Result<usize> {
+        let n = self.inner.write_vectored(bufs)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,245 @@
+use std::io::{Read, Error, ErrorKind};
+use std::str;
+
+use byteorder::{LE, BE};
+
+use auto::{sniff_bom, UTF8_BOM};
+use endian::Endianness;
+use prefixed::{PrefixedReader, read_up_to};
+use read::{decode_char, DecodeState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The encoding of text that's either utf-16 or utf-8
+pub enum Encoding {
+    /// Little endian utf-16
+    Utf16Le,
+    /// Big endian utf-16
+    Utf16Be,
+    /// utf-8
+    Utf8,
+}
+
+impl From<Endianness> for Encoding {
+    fn from(e: Endianness) -> Self {
+        match e {
+            Endianness::Little => Encoding::Utf16Le,
+            Endianness::Big => Encoding::Utf16Be,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A reader of text in an encoding detected from its bom, which can be utf-16 or utf-8
+///
+/// `FF FE` and `FE FF` are read as utf-16 like in `AutoEndianReader::new_auto_bom`,
+/// and `EF BB BF` is read as utf-8. Either way, the text is read as `char`s,
+/// so the same code works for both encodings.
+pub struct AnyEncodingReader<R> {
+    inner: PrefixedReader<R>,
+    encoding: Encoding,
+    /// A byte that was read as part of a malformed utf-8 sequence but starts the next one
+    pending: Option<u8>,
+    state: DecodeState,
+}
+
+impl<R: Read> AnyEncodingReader<R> {
+    /// Reads the bom to detect the encoding, falling back to `default` if there is no bom
+    ///
+    /// The bom is dropped. If there is none, the bytes that were read are kept
+    /// and will be read again as text.
+    pub fn new(mut inner: R, default: Encoding) -> Result<Self, Error> {
+        let (found, bom, n) = sniff_bom(&mut inner)?;
+        let (encoding, prefix) = match found {
+            Some(e) => (e.into(), &[][..]),
+            None if bom[..n] == UTF8_BOM => (Encoding::Utf8, &[][..]),
+            None => (default, &bom[..n]),
+        };
+        Ok(AnyEncodingReader {
+            inner: PrefixedReader::new(prefix, inner),
+            encoding,
+            pending: None,
+            state: DecodeState::default(),
+        })
+    }
+    /// Reads the next `char`, returning `None` at EOF
+    ///
+    /// Malformed text is an error with `ErrorKind::InvalidData`, and reading continues
+    /// right after it. Reads failing with `ErrorKind::Interrupted` are retried.
+    pub fn read_char(&mut self) -> Option<Result<char, Error>> {
+        match self.encoding {
+            Encoding::Utf16Le => {
+                decode_char::<LE, _>(&mut self.inner, &mut self.state).map(|d| d.result)
+            }
+            Encoding::Utf16Be => {
+                decode_char::<BE, _>(&mut self.inner, &mut self.state).map(|d| d.result)
+            }
+            Encoding::Utf8 => self.read_utf8_char(),
+        }
+    }
+    fn read_utf8_char(&mut self) -> Option<Result<char, Error>> {
+        let mut buf = [0; 4];
+        match self.pending.take() {
+            Some(b) => buf[0] = b,
+            None => match read_up_to(&mut self.inner, &mut buf[..1]) {
+                Ok(0) => return None,
+                Ok(_) => (),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        let len = match buf[0] {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return Some(Err(invalid_utf8())),
+        };
+        // The continuation bytes are read one at a time, so a byte that isn't one
+        // can be kept as the start of the next char
+        for i in 1..len {
+            match read_up_to(&mut self.inner, &mut buf[i..i + 1]) {
+                Ok(0) => {
+                    let msg = "stream ended in the middle of a char";
+                    return Some(Err(Error::new(ErrorKind::UnexpectedEof, msg)))
+                }
+                Ok(_) if buf[i] & 0xc0 != 0x80 => {
+                    self.pending = Some(buf[i]);
+                    return Some(Err(invalid_utf8()))
+                }
+                Ok(_) => (),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        Some(match str::from_utf8(&buf[..len]) {
+            Ok(s) => Ok(s.chars().next().unwrap()),
+            Err(e) => Err(Error::new(ErrorKind::InvalidData, e)),
+        })
+    }
+    /// Reads all chars until a newline is reached (U+000A) and appends them to the provided buffer
+    ///
+    /// This behaves like `Utf16ReadExt::read_utf16_line` no matter the encoding,
+    /// so it returns the number of chars appended, including the newline.
+    pub fn read_line(&mut self, buf: &mut String) -> Result<usize, Error> {
+        let mut len = 0;
+        while let Some(c) = self.read_char() {
+            let c = c?;
+            buf.push(c);
+            len += 1;
+            if c == '\n' {
+                break
+            }
+        }
+        Ok(len)
+    }
+    /// Transforms this reader into an `Iterator` over its `char`s
+    pub fn chars(self) -> AnyEncodingChars<R> {
+        AnyEncodingChars(self)
+    }
+}
+
+impl<R> AnyEncodingReader<R> {
+    /// Returns the encoding that was detected, or the default one if there was no bom
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+    /// Unwraps this reader, returning the underlying reader
+    ///
+    /// Bytes that were read ahead but not decoded yet are lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over the `char`s of an `AnyEncodingReader`
+pub struct AnyEncodingChars<R>(AnyEncodingReader<R>);
+
+impl<R> AnyEncodingChars<R> {
+    /// Returns the encoding the chars are decoded from
+    pub fn encoding(&self) -> Encoding {
+        self.0.encoding
+    }
+    /// Unwraps this iterator, returning the `AnyEncodingReader`
+    pub fn into_inner(self) -> AnyEncodingReader<R> {
+        self.0
+    }
+}
+
+impl<R: Read> Iterator for AnyEncodingChars<R> {
+    type Item = Result<char, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.read_char()
+    }
+}
+
+fn invalid_utf8() -> Error {
+    Error::new(ErrorKind::InvalidData, "invalid utf-8 sequence")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, ErrorKind};
+
+    use super::*;
+    use test_util::*;
+
+    fn read_all(bytes: Vec<u8>, default: Encoding) -> (Encoding, Vec<Result<char, ErrorKind>>) {
+        let r = AnyEncodingReader::new(Cursor::new(bytes), default).unwrap();
+        let encoding = r.encoding();
+        (encoding, r.chars().map(|c| c.map_err(|e| e.kind())).collect())
+    }
+
+    #[test]
+    fn each_bom_picks_its_encoding() {
+        let text = "a\u{1f600}";
+        let chars = vec![Ok('a'), Ok('\u{1f600}')];
+
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend(le_str(text));
+        assert_eq!(read_all(bytes, Encoding::Utf8), (Encoding::Utf16Le, chars.clone()));
+
+        let mut bytes = vec![0xfe, 0xff];
+        bytes.extend(text.encode_utf16().flat_map(|u| u.to_be_bytes().to_vec()));
+        assert_eq!(read_all(bytes, Encoding::Utf8), (Encoding::Utf16Be, chars.clone()));
+
+        let mut bytes = UTF8_BOM.to_vec();
+        bytes.extend(text.bytes());
+        assert_eq!(read_all(bytes, Encoding::Utf16Le), (Encoding::Utf8, chars));
+    }
+
+    #[test]
+    fn no_bom_falls_back_to_the_default_and_keeps_the_bytes() {
+        let chars = vec![Ok('a'), Ok('b')];
+        assert_eq!(read_all(le_str("ab"), Encoding::Utf16Le), (Encoding::Utf16Le, chars));
+        // The first two bytes of a utf-8 bom that isn't finished are text too
+        let bytes = vec![0xef, 0xbb, b'a'];
+        let (encoding, chars) = read_all(bytes, Encoding::Utf8);
+        assert_eq!(encoding, Encoding::Utf8);
+        assert_eq!(chars, [Err(ErrorKind::InvalidData), Ok('a')]);
+        assert_eq!(read_all(Vec::new(), Encoding::Utf16Be), (Encoding::Utf16Be, Vec::new()));
+    }
+
+    #[test]
+    fn invalid_continuation_byte_is_read_again_as_the_next_char() {
+        let mut bytes = UTF8_BOM.to_vec();
+        bytes.extend(&[0xe2, 0x82, b'a', 0xc3, 0xa9, 0x80]);
+        let (_, chars) = read_all(bytes, Encoding::Utf16Le);
+        let invalid = Err(ErrorKind::InvalidData);
+        assert_eq!(chars, [invalid, Ok('a'), Ok('é'), invalid]);
+    }
+
+    #[test]
+    fn utf8_stream_ending_in_a_char_is_unexpected_eof() {
+        let mut bytes = UTF8_BOM.to_vec();
+        bytes.extend(&[b'a', 0xf0, 0x9f]);
+        let (_, chars) = read_all(bytes, Encoding::Utf16Le);
+        assert_eq!(chars, [Ok('a'), Err(ErrorKind::UnexpectedEof)]);
+    }
+}
@@ -0,0 +1,86 @@
+/// The byte order mark, U+FEFF
+///
+/// Read in the wrong endianness it becomes `0xFFFE`, which is a non-character,
+/// so seeing that means the data has the opposite endianness.
+pub const BOM: u16 = 0xfeff;
+
+/// Detects the endianness from a short that was read in native endianness
+///
+/// `BOM` means the data is in native endianness and a byte-swapped `BOM` means it's
+/// in the opposite one. Anything else isn't a bom and gives `None`.
+pub fn detect_bom(short: u16) -> Option<Endianness> {
+    match classify_leading_short(short) {
+        LeadingShort::BomLittle => Some(Endianness::Little),
+        LeadingShort::BomBig => Some(Endianness::Big),
+        LeadingShort::Data(_) => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// What the first short of a stream turned out to be
+pub enum LeadingShort {
+    /// A little endian bom
+    BomLittle,
+    /// A big endian bom
+    BomBig,
+    /// Not a bom, so the short is data
+    Data(u16),
+}
+
+/// Classifies the first short of a stream, which was read in native endianness
+///
+/// Like `detect_bom` but a short that isn't a bom is given back as `LeadingShort::Data`,
+/// so a caller that did the reading itself can keep it as the first short of the data.
+pub fn classify_leading_short(short: u16) -> LeadingShort {
+    match Endianness::from_bom_bytes(short.to_ne_bytes()) {
+        Some(Endianness::Little) => LeadingShort::BomLittle,
+        Some(Endianness::Big) => LeadingShort::BomBig,
+        None => LeadingShort::Data(short),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The byte order of utf-16 data known at runtime
+pub enum Endianness {
+    /// Little endian
+    Little,
+    /// Big endian
+    Big,
+}
+
+impl Endianness {
+    /// Detects the endianness from the two bytes of a BOM (U+FEFF)
+    ///
+    /// Returns `None` if the bytes aren't a BOM in either endianness
+    pub fn from_bom_bytes(b: [u8; 2]) -> Option<Endianness> {
+        match b {
+            [0xff, 0xfe] => Some(Endianness::Little),
+            [0xfe, 0xff] => Some(Endianness::Big),
+            _ => None,
+        }
+    }
+    /// Returns the bytes of a BOM (U+FEFF) in this endianness
+    pub fn to_bom_bytes(self) -> [u8; 2] {
+        match self {
+            Endianness::Little => [0xff, 0xfe],
+            Endianness::Big => [0xfe, 0xff],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bom_is_detected_in_both_orders() {
+        assert_eq!(BOM, 0xfeff);
+        let little = u16::from_ne_bytes(BOM.to_le_bytes());
+        let big = u16::from_ne_bytes(BOM.to_be_bytes());
+        assert_eq!(detect_bom(little), Some(Endianness::Little));
+        assert_eq!(detect_bom(big), Some(Endianness::Big));
+        assert_eq!(detect_bom(0x0041), None);
+        let big = Endianness::Big.to_bom_bytes();
+        assert_eq!(Endianness::from_bom_bytes(big), Some(Endianness::Big));
+    }
+}
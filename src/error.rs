@@ -0,0 +1,120 @@
+use std::error;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Malformed utf-16: a surrogate that isn't part of a valid surrogate pair
+pub struct Utf16DecodeError {
+    unit: u16,
+    offset: u64,
+}
+
+impl Utf16DecodeError {
+    pub(crate) fn new(unit: u16, offset: u64) -> Self {
+        Utf16DecodeError { unit, offset }
+    }
+    /// Returns the unpaired surrogate
+    pub fn unpaired_surrogate(&self) -> u16 {
+        self.unit
+    }
+    /// Returns the position of the unpaired surrogate, counted in shorts
+    /// from where decoding started
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl fmt::Display for Utf16DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unpaired surrogate {:#06x} at short {}", self.unit, self.offset)
+    }
+}
+
+impl error::Error for Utf16DecodeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A line that was longer than it was allowed to be
+pub struct LineTooLong {
+    max_len: usize,
+}
+
+impl LineTooLong {
+    pub(crate) fn new(max_len: usize) -> Self {
+        LineTooLong { max_len }
+    }
+    /// Returns the maximum number of chars the line was allowed to have
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+}
+
+impl fmt::Display for LineTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line exceeded maximum length of {} chars", self.max_len)
+    }
+}
+
+impl error::Error for LineTooLong {}
+
+/// A line that's too long becomes an error with `ErrorKind::InvalidData` carrying the `LineTooLong`
+impl From<LineTooLong> for Error {
+    fn from(e: LineTooLong) -> Self {
+        Error::new(ErrorKind::InvalidData, e)
+    }
+}
+
+#[derive(Debug)]
+/// An error from reading utf-16 that tells I/O errors apart from malformed utf-16
+pub enum Utf16Error {
+    /// The reader failed, or the stream ended in the middle of a short
+    Io(Error),
+    /// The utf-16 was malformed
+    Decode(Utf16DecodeError),
+}
+
+impl fmt::Display for Utf16Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Utf16Error::Io(ref e) => e.fmt(f),
+            Utf16Error::Decode(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Utf16Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Utf16Error::Io(ref e) => Some(e),
+            Utf16Error::Decode(ref e) => Some(e),
+        }
+    }
+}
+
+impl From<Error> for Utf16Error {
+    fn from(e: Error) -> Self {
+        Utf16Error::Io(e)
+    }
+}
+
+impl From<Utf16DecodeError> for Utf16Error {
+    fn from(e: Utf16DecodeError) -> Self {
+        Utf16Error::Decode(e)
+    }
+}
+
+/// A decode error becomes an error with `ErrorKind::InvalidData` carrying the `Utf16DecodeError`
+impl From<Utf16DecodeError> for Error {
+    fn from(e: Utf16DecodeError) -> Self {
+        Error::new(ErrorKind::InvalidData, e)
+    }
+}
+
+/// A decode error becomes an error with `ErrorKind::InvalidData` carrying the `Utf16DecodeError`
+impl From<Utf16Error> for Error {
+    fn from(e: Utf16Error) -> Self {
+        match e {
+            Utf16Error::Io(e) => e,
+            Utf16Error::Decode(e) => e.into(),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+use std::io::{Write, Result, Error};
+use std::marker::PhantomData;
+
+use byteorder::ByteOrder;
+
+use write::write_str;
+
+#[derive(Debug)]
+/// A writer implementing `fmt::Write` that writes utf-16 in endianness `T` to the inner writer
+///
+/// This allows using `write!` and `writeln!` to write utf-16. Since `fmt::Write` can't
+/// return the I/O error, the first one is stored and can be retrieved with `into_result`.
+/// Once an error has happened, nothing more is written.
+pub struct Utf16Formatter<T: ByteOrder, W> {
+    inner: W,
+    error: Option<Error>,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, W: Write> Utf16Formatter<T, W> {
+    /// Makes a new `Utf16Formatter` writing to `inner`
+    pub fn new(inner: W) -> Self {
+        Utf16Formatter {
+            inner,
+            error: None,
+            _order: PhantomData,
+        }
+    }
+    /// Gets a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying writer
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+    /// Returns the stored I/O error, if any
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+    /// Unwraps this `Utf16Formatter`, returning the underlying writer,
+    /// or the first I/O error that happened while writing
+    pub fn into_result(self) -> Result<W> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.inner),
+        }
+    }
+}
+
+impl<T: ByteOrder, W: Write> fmt::Write for Utf16Formatter<T, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.error.is_some() {
+            return Err(fmt::Error)
+        }
+        write_str::<T, _>(&mut self.inner, s).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
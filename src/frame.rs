@@ -0,0 +1,122 @@
+use std::io::{Write, Seek, SeekFrom, Result, Error, ErrorKind};
+use std::marker::PhantomData;
+
+use byteorder::{ByteOrder, WriteBytesExt};
+
+use write::Utf16WriteExt;
+
+#[derive(Debug)]
+/// A writer of utf-16 frames prefixed with their length in code units as a `u32`
+///
+/// `begin_frame` reserves room for the length, which is written by `end_frame`
+/// once the body has been written, by seeking back. Both the length and the body are
+/// written in endianness `T`.
+pub struct LengthPrefixedWriter<T: ByteOrder, W> {
+    inner: W,
+    start: Option<u64>,
+    units: u64,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, W: Write + Seek> LengthPrefixedWriter<T, W> {
+    /// Makes a new `LengthPrefixedWriter` writing to `inner`
+    pub fn new(inner: W) -> Self {
+        LengthPrefixedWriter {
+            inner,
+            start: None,
+            units: 0,
+            _order: PhantomData,
+        }
+    }
+    /// Starts a new frame by reserving 4 bytes for its length
+    ///
+    /// Errors with `ErrorKind::InvalidInput` if a frame has already been begun.
+    pub fn begin_frame(&mut self) -> Result<()> {
+        if self.start.is_some() {
+            return Err(Error::new(ErrorKind::InvalidInput, "a frame has already been begun"))
+        }
+        let start = self.inner.stream_position()?;
+        self.inner.write_u32::<T>(0)?;
+        self.start = Some(start);
+        self.units = 0;
+        Ok(())
+    }
+    /// Writes `u16`s to the body of the current frame
+    pub fn write_shorts(&mut self, buf: &[u16]) -> Result<()> {
+        if self.start.is_none() {
+            return Err(no_frame())
+        }
+        self.inner.write_all_shorts::<T>(buf)?;
+        self.units += buf.len() as u64;
+        Ok(())
+    }
+    /// Writes a string as UTF-16 to the body of the current frame
+    pub fn write_utf16_string(&mut self, s: &str) -> Result<()> {
+        let mut buf = [0; 2];
+        for c in s.chars() {
+            self.write_shorts(c.encode_utf16(&mut buf))?;
+        }
+        Ok(())
+    }
+    /// Ends the current frame by writing its length before it
+    ///
+    /// Returns the length of the frame in code units.
+    pub fn end_frame(&mut self) -> Result<u32> {
+        let start = self.start.take().ok_or_else(no_frame)?;
+        let units = self.units;
+        if units > u64::from(u32::MAX) {
+            return Err(Error::new(ErrorKind::InvalidData, "frame is too long for its length"))
+        }
+        let end = self.inner.stream_position()?;
+        self.inner.seek(SeekFrom::Start(start))?;
+        self.inner.write_u32::<T>(units as u32)?;
+        self.inner.seek(SeekFrom::Start(end))?;
+        Ok(units as u32)
+    }
+    /// Returns the inner writer
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+fn no_frame() -> Error {
+    Error::new(ErrorKind::InvalidInput, "no frame has been begun")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use byteorder::{LE, ReadBytesExt};
+    use read::Utf16ReadExt;
+
+    #[test]
+    fn patched_length_matches_the_body() {
+        let mut w = LengthPrefixedWriter::<LE, _>::new(Cursor::new(Vec::new()));
+        for s in &["a\u{1f600}b", ""] {
+            w.begin_frame().unwrap();
+            w.write_utf16_string(s).unwrap();
+            assert_eq!(w.end_frame().unwrap() as usize, s.encode_utf16().count());
+        }
+        let mut r = Cursor::new(w.into_inner().into_inner());
+        for s in &["a\u{1f600}b", ""] {
+            let mut body = vec![0; r.read_u32::<LE>().unwrap() as usize];
+            r.read_exact_shorts::<LE>(&mut body).unwrap();
+            assert_eq!(String::from_utf16(&body).unwrap(), *s);
+        }
+        assert_eq!(r.read_u8().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn frames_cant_be_nested_or_ended_twice() {
+        let mut w = LengthPrefixedWriter::<LE, _>::new(Cursor::new(Vec::new()));
+        assert_eq!(w.end_frame().unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(w.write_shorts(&[0x61]).unwrap_err().kind(), ErrorKind::InvalidInput);
+        w.begin_frame().unwrap();
+        assert_eq!(w.begin_frame().unwrap_err().kind(), ErrorKind::InvalidInput);
+        w.write_shorts(&[0x61]).unwrap();
+        assert_eq!(w.end_frame().unwrap(), 1);
+        assert_eq!(w.end_frame().unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(w.into_inner().into_inner(), [1, 0, 0, 0, 0x61, 0]);
+    }
+}
@@ -0,0 +1,183 @@
+use std::io::{Read, Error};
+
+use byteorder::{LE, BE, ReadBytesExt};
+
+use auto::{AutoEndianReader, sniff_bom, no_bom, utf8_bom, UTF8_BOM};
+use endian::Endianness;
+use prefixed::PrefixedReader;
+use read::Utf16ReadExt;
+
+#[derive(Debug)]
+/// A reader that detects its endianness from a bom the first time it's read from
+///
+/// Unlike `AutoEndianReader::new_auto_bom`, making one never fails, since the stream
+/// isn't touched until the first read. Errors from detecting the bom are returned from
+/// that read instead. If detection fails, the bytes that were read are kept, so the next
+/// read tries again and fails in the same way.
+pub struct LazyAutoEndianReader<R> {
+    inner: PrefixedReader<R>,
+    endianness: Option<Endianness>,
+    default: Option<Endianness>,
+}
+
+impl<R: Read> LazyAutoEndianReader<R> {
+    /// Makes a new `LazyAutoEndianReader` that requires the stream to start with a bom
+    pub fn new(inner: R) -> Self {
+        LazyAutoEndianReader {
+            inner: PrefixedReader::new(&[], inner),
+            endianness: None,
+            default: None,
+        }
+    }
+    /// Makes a new `LazyAutoEndianReader` that falls back to `default` if there is no bom
+    ///
+    /// Like in `AutoEndianReader::new_auto_bom_or`, the bytes that were read are then
+    /// read again as data.
+    pub fn with_default(inner: R, default: Endianness) -> Self {
+        LazyAutoEndianReader {
+            inner: PrefixedReader::new(&[], inner),
+            endianness: None,
+            default: Some(default),
+        }
+    }
+    /// Returns the endianness, reading the bom first if that hasn't been done yet
+    pub fn endianness(&mut self) -> Result<Endianness, Error> {
+        if let Some(e) = self.endianness {
+            return Ok(e)
+        }
+        let (found, bom, n) = sniff_bom(&mut self.inner)?;
+        if let Some(e) = found {
+            self.endianness = Some(e);
+            return Ok(e)
+        }
+        // Whatever happens, the bytes that were read aren't a bom, so they're data
+        self.inner.put_back(&bom[..n]);
+        if bom[..n] == UTF8_BOM {
+            return Err(utf8_bom())
+        }
+        match self.default {
+            Some(e) => {
+                self.endianness = Some(e);
+                Ok(e)
+            }
+            None => Err(no_bom()),
+        }
+    }
+    /// Returns the endianness if it has been detected already
+    pub fn detected_endianness(&self) -> Option<Endianness> {
+        self.endianness
+    }
+    /// Mirror of `Utf16ReadExt::read_u16` without the type parameter for endianness
+    pub fn read_u16(&mut self) -> Result<u16, Error> {
+        match self.endianness()? {
+            Endianness::Little => self.inner.read_u16::<LE>(),
+            Endianness::Big => self.inner.read_u16::<BE>(),
+        }
+    }
+    /// Mirror of `Utf16ReadExt::read_utf16_line` without the type parameter for endianness
+    pub fn read_utf16_line(&mut self, buf: &mut String) -> Result<usize, Error> {
+        match self.endianness()? {
+            Endianness::Little => self.inner.read_utf16_line::<LE>(buf),
+            Endianness::Big => self.inner.read_utf16_line::<BE>(buf),
+        }
+    }
+    /// Mirror of `Utf16ReadExt::read_utf16_to_string` without the type parameter for endianness
+    pub fn read_utf16_to_string(&mut self, buf: &mut String) -> Result<usize, Error> {
+        match self.endianness()? {
+            Endianness::Little => self.inner.read_utf16_to_string::<LE>(buf),
+            Endianness::Big => self.inner.read_utf16_to_string::<BE>(buf),
+        }
+    }
+    /// Detects the endianness if needed and turns this into an `AutoEndianReader`
+    ///
+    /// Use this to get the iterators of `AutoEndianReader`.
+    pub fn into_auto_endian_reader(mut self) -> Result<AutoEndianReader<PrefixedReader<R>>, Error> {
+        let e = self.endianness()?;
+        Ok(AutoEndianReader::new(self.inner, e))
+    }
+}
+
+impl<R> LazyAutoEndianReader<R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+    /// Unwraps this reader, returning the underlying reader
+    ///
+    /// Bytes that were read while looking for a bom but turned out to be data are lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, ErrorKind};
+
+    use super::*;
+    use test_util::*;
+
+    #[test]
+    fn bom_is_read_on_first_use() {
+        let mut bytes = vec![0xfe, 0xff];
+        bytes.extend(&[0, b'a', 0, b'\n']);
+        let mut r = LazyAutoEndianReader::new(Cursor::new(bytes));
+        assert_eq!(r.detected_endianness(), None);
+        let mut line = String::new();
+        assert_eq!(r.read_utf16_line(&mut line).unwrap(), 2);
+        assert_eq!(line, "a\n");
+        assert_eq!(r.detected_endianness(), Some(Endianness::Big));
+    }
+
+    #[test]
+    fn no_bom_with_a_default_keeps_the_bytes_as_data() {
+        let r = Cursor::new(le_str("ab"));
+        let mut r = LazyAutoEndianReader::with_default(r, Endianness::Little);
+        let mut s = String::new();
+        assert_eq!(r.read_utf16_to_string(&mut s).unwrap(), 2);
+        assert_eq!(s, "ab");
+        assert_eq!(r.detected_endianness(), Some(Endianness::Little));
+    }
+
+    #[test]
+    fn no_bom_without_a_default_fails_the_same_way_every_time() {
+        let mut r = LazyAutoEndianReader::new(Cursor::new(le_str("ab")));
+        for _ in 0..2 {
+            let e = r.read_u16().unwrap_err();
+            assert_eq!(e.kind(), ErrorKind::InvalidData);
+            assert_eq!(e.to_string(), no_bom().to_string());
+        }
+        assert_eq!(r.detected_endianness(), None);
+        assert_eq!(r.get_ref().position(), 2);
+    }
+
+    #[test]
+    fn utf8_bom_is_an_error_even_with_a_default() {
+        let mut bytes = UTF8_BOM.to_vec();
+        bytes.push(b'a');
+        let mut r = LazyAutoEndianReader::with_default(Cursor::new(bytes), Endianness::Little);
+        for _ in 0..2 {
+            assert_eq!(r.endianness().unwrap_err().to_string(), utf8_bom().to_string());
+        }
+        assert_eq!(r.detected_endianness(), None);
+    }
+
+    #[test]
+    fn stream_too_short_for_a_bom() {
+        let mut r = LazyAutoEndianReader::new(Cursor::new(vec![0xff]));
+        assert_eq!(r.endianness().unwrap_err().kind(), ErrorKind::InvalidData);
+
+        let mut r = LazyAutoEndianReader::with_default(Cursor::new(vec![0xff]), Endianness::Big);
+        assert_eq!(r.endianness().unwrap(), Endianness::Big);
+        assert_eq!(r.read_u16().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+
+        let mut r = LazyAutoEndianReader::with_default(Cursor::new(Vec::new()), Endianness::Big);
+        let mut s = String::new();
+        assert_eq!(r.read_utf16_to_string(&mut s).unwrap(), 0);
+        assert_eq!(r.detected_endianness(), Some(Endianness::Big));
+    }
+}
@@ -4,9 +4,49 @@
 pub extern crate byteorder;
 
 mod auto;
+mod bomaware;
+mod builder;
+mod buffered;
+mod counting;
+mod encoding;
+mod endian;
+mod error;
+mod formatter;
+mod frame;
+mod lazy;
+mod lines;
+mod prefixed;
+mod pstring;
 mod read;
+mod seekable;
+mod sink;
+mod sized;
+mod slice;
+#[cfg(test)]
+mod test_util;
+mod transcode;
+mod typed;
 mod write;
 
 pub use auto::*;
+pub use bomaware::*;
+pub use builder::*;
+pub use buffered::*;
+pub use counting::*;
+pub use encoding::*;
+pub use endian::*;
+pub use error::*;
+pub use formatter::*;
+pub use frame::*;
+pub use lazy::*;
+pub use lines::*;
+pub use prefixed::*;
+pub use pstring::*;
 pub use read::*;
+pub use seekable::*;
+pub use sink::*;
+pub use sized::*;
+pub use slice::*;
+pub use transcode::*;
+pub use typed::*;
 pub use write::*;
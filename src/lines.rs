@@ -0,0 +1,471 @@
+use std::char::REPLACEMENT_CHARACTER;
+use std::io::{Seek, SeekFrom, Error, ErrorKind};
+use std::marker::PhantomData;
+
+use byteorder::ByteOrder;
+
+use error::LineTooLong;
+use read::{Utf16ReadExt, TrimMode, LineEndingKind, Decoded, DecodeState, decode_char};
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LineOptions {
+    pub delimiter: char,
+    /// Which line endings end a line, overriding `delimiter`
+    pub recognize: Option<LineEndingKind>,
+    pub max_len: Option<usize>,
+    pub trim: TrimMode,
+    pub keep_ends: bool,
+    pub strip_leading_bom: bool,
+    pub normalize: bool,
+    pub lossy: bool,
+    pub capacity: usize,
+}
+
+impl Default for LineOptions {
+    fn default() -> Self {
+        LineOptions {
+            delimiter: '\n',
+            recognize: None,
+            max_len: None,
+            trim: TrimMode::None,
+            keep_ends: false,
+            strip_leading_bom: false,
+            normalize: false,
+            lossy: false,
+            capacity: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A builder for an iterator over the lines of a reader (reading utf-16)
+///
+/// Made by `Utf16ReadExt::utf16_lines_builder`. Without changing any options,
+/// the built iterator behaves like the one returned by `Utf16ReadExt::utf16_lines`.
+pub struct LinesBuilder<T: ByteOrder, R> {
+    inner: R,
+    options: LineOptions,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, R> LinesBuilder<T, R> {
+    pub(crate) fn new(inner: R) -> Self {
+        LinesBuilder {
+            inner,
+            options: LineOptions::default(),
+            _order: PhantomData,
+        }
+    }
+    /// Sets the char that ends a line (U+000A by default)
+    ///
+    /// A `\r` before the delimiter is only treated as part of the line ending
+    /// if the delimiter is U+000A. This undoes `line_endings`.
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.options.delimiter = delimiter;
+        self.options.recognize = None;
+        self
+    }
+    /// Sets which newline sequences end a line, like in `Utf16ReadExt::utf16_lines_with`
+    ///
+    /// By default, `\n` and `\r\n` end a line. This undoes `delimiter`.
+    pub fn line_endings(mut self, recognize: LineEndingKind) -> Self {
+        self.options.delimiter = '\n';
+        self.options.recognize = Some(recognize);
+        self
+    }
+    /// Sets the maximum number of chars a line may have, not counting the line ending
+    ///
+    /// A `\r` of a `\r\n` line ending isn't counted either. Reading a line that is longer
+    /// yields an error with `ErrorKind::InvalidData` carrying a `LineTooLong`, and the rest
+    /// of that line is skipped, so the next item is the line after it.
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.options.max_len = Some(max_len);
+        self
+    }
+    /// Sets how whitespace is trimmed from each line (not trimmed by default)
+    pub fn trim(mut self, mode: TrimMode) -> Self {
+        self.options.trim = mode;
+        self
+    }
+    /// Sets whether line endings should be kept at the end of each line (off by default)
+    pub fn keep_ends(mut self, keep_ends: bool) -> Self {
+        self.options.keep_ends = keep_ends;
+        self
+    }
+    /// Sets whether a BOM (U+FEFF) at the start of the first line should be dropped
+    /// (off by default)
+    pub fn strip_leading_bom(mut self, strip: bool) -> Self {
+        self.options.strip_leading_bom = strip;
+        self
+    }
+    /// Sets whether kept `\r\n` line endings should be normalized to `\n` (off by default)
+    ///
+    /// This has no effect unless line endings are kept.
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.options.normalize = normalize;
+        self
+    }
+    /// Sets whether malformed utf-16 should be replaced with U+FFFD instead of
+    /// being yielded as an error (off by default)
+    pub fn lossy(mut self, lossy: bool) -> Self {
+        self.options.lossy = lossy;
+        self
+    }
+    /// Sets how many bytes the `String` of every line starts out with room for (0 by default)
+    pub fn capacity(mut self, cap: usize) -> Self {
+        self.options.capacity = cap;
+        self
+    }
+    /// Builds the iterator
+    pub fn build(self) -> ConfiguredLines<T, R> {
+        ConfiguredLines::new(self.inner, self.options)
+    }
+    /// Builds an iterator that yields every line along with its line number
+    pub fn build_numbered(self) -> NumberedLines<T, R> {
+        NumberedLines(self.build())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How a line read by `ConfiguredLines` ended
+pub(crate) enum Ending {
+    /// The stream ended
+    None,
+    /// The delimiter, which is `\n` unless another one was set
+    Delimiter,
+    /// A lone `\r`, with `LineEndingKind::Any`
+    Cr,
+    /// `\r\n`
+    CrLf,
+}
+
+#[derive(Debug, Clone)]
+/// An iterator over the lines of a reader configured by a `LinesBuilder` (reading utf-16)
+///
+/// This is what all line iterators of `Utf16ReadExt` are built on.
+pub struct ConfiguredLines<T: ByteOrder, R> {
+    inner: R,
+    options: LineOptions,
+    first: bool,
+    /// Set after a line was too long, so the rest of it is skipped
+    skip_line: bool,
+    /// A char read after a lone `\r`, which starts the next line
+    peeked: Option<char>,
+    /// The number of lines yielded so far, counting lines that were too long
+    line: usize,
+    state: DecodeState,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, R> ConfiguredLines<T, R> {
+    pub(crate) fn new(inner: R, options: LineOptions) -> Self {
+        ConfiguredLines {
+            inner,
+            options,
+            first: true,
+            skip_line: false,
+            peeked: None,
+            line: 0,
+            state: DecodeState::default(),
+            _order: PhantomData,
+        }
+    }
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    ///
+    /// With `LineEndingKind::Any`, the char after a `\r` has to be read to see if it's
+    /// a `\n`, so that char is lost if it isn't.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+    /// Replaces the underlying reader with what `f` returns for it
+    ///
+    /// This is useful for wrapping the reader in another one partway through.
+    pub fn map_inner<R2, F: FnOnce(R) -> R2>(self, f: F) -> ConfiguredLines<T, R2> {
+        ConfiguredLines {
+            inner: f(self.inner),
+            options: self.options,
+            first: self.first,
+            skip_line: self.skip_line,
+            peeked: self.peeked,
+            line: self.line,
+            state: self.state,
+            _order: PhantomData,
+        }
+    }
+    /// Returns the number of the last line yielded, starting from 1
+    ///
+    /// A line that was yielded as too long is counted, but one that was interrupted
+    /// by any other error isn't, since the rest of it is yielded as the next item.
+    pub fn line_number(&self) -> usize {
+        self.line
+    }
+}
+
+/// Seeks the underlying reader
+///
+/// Anything read ahead is dropped, and so is the rest of a line that was too long,
+/// so the next line starts where the reader was seeked to.
+impl<T: ByteOrder, R: Seek> Seek for ConfiguredLines<T, R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.skip_line = false;
+        self.peeked = None;
+        self.state = DecodeState::default();
+        self.inner.seek(pos)
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> ConfiguredLines<T, R> {
+    fn next_char(&mut self) -> Option<Result<char, Error>> {
+        if let Some(c) = self.peeked.take() {
+            return Some(Ok(c))
+        }
+        loop {
+            return match decode_char::<T, _>(&mut self.inner, &mut self.state)? {
+                Decoded { result: Ok(c), .. } => Some(Ok(c)),
+                Decoded { malformed: true, .. } if self.options.lossy => {
+                    Some(Ok(REPLACEMENT_CHARACTER))
+                }
+                Decoded { result: Err(e), .. } => match e.kind() {
+                    ErrorKind::Interrupted => continue,
+                    _ => Some(Err(e)),
+                }
+            }
+        }
+    }
+    /// Reads the next line into `buf`, which should be empty, and returns how it ended,
+    /// or `None` at EOF
+    ///
+    /// On error, `buf` holds the part of the line read before it.
+    pub(crate) fn read_line(&mut self, buf: &mut String) -> Option<Result<Ending, Error>> {
+        let opts = self.options;
+        // Whether a `\r` may start the line ending
+        let cr_ends = opts.delimiter == '\n' && opts.recognize != Some(LineEndingKind::Lf);
+        let mut read_any = false;
+        let mut len = 0;
+        // A `\r` that isn't in `buf` until it turns out not to be part of the line ending
+        let mut cr = false;
+        let mut ending = loop {
+            let c = match self.next_char() {
+                None => break Ending::None,
+                Some(Ok(c)) => c,
+                Some(Err(e)) => return Some(Err(e)),
+            };
+            read_any = true;
+            if self.first {
+                self.first = false;
+                if opts.strip_leading_bom && c == '\u{feff}' {
+                    continue
+                }
+            }
+            let ending = if cr && c == '\n' {
+                Some(Ending::CrLf)
+            } else if cr && opts.recognize == Some(LineEndingKind::Any) {
+                self.peeked = Some(c);
+                Some(Ending::Cr)
+            } else if c == opts.delimiter && opts.recognize != Some(LineEndingKind::CrLf) {
+                Some(Ending::Delimiter)
+            } else {
+                None
+            };
+            if let Some(ending) = ending {
+                if !self.skip_line {
+                    break ending
+                }
+                // The rest of a line that was too long has been skipped
+                self.skip_line = false;
+                read_any = false;
+                cr = false;
+                continue
+            }
+            if cr {
+                cr = false;
+                if let Some(e) = self.push(buf, '\r', &mut len) {
+                    return Some(Err(e))
+                }
+            }
+            if c == '\r' && cr_ends {
+                cr = true;
+            } else if let Some(e) = self.push(buf, c, &mut len) {
+                return Some(Err(e))
+            }
+        };
+        // A `\r` at the end of the stream
+        if cr && ending == Ending::None && !self.skip_line {
+            if opts.recognize == Some(LineEndingKind::Any) {
+                ending = Ending::Cr;
+            } else if let Some(e) = self.push(buf, '\r', &mut len) {
+                return Some(Err(e))
+            }
+        }
+        if !read_any || self.skip_line {
+            self.skip_line = false;
+            return None
+        }
+        self.line += 1;
+        opts.trim.trim(buf);
+        if opts.keep_ends {
+            match ending {
+                Ending::None => (),
+                Ending::Delimiter => buf.push(opts.delimiter),
+                Ending::Cr => buf.push('\r'),
+                Ending::CrLf if opts.normalize => buf.push('\n'),
+                Ending::CrLf => buf.push_str("\r\n"),
+            }
+        }
+        Some(Ok(ending))
+    }
+    /// Appends `c` to the line, unless the line is already as long as it may be
+    fn push(&mut self, buf: &mut String, c: char, len: &mut usize) -> Option<Error> {
+        if self.skip_line {
+            return None
+        }
+        if let Some(max) = self.options.max_len {
+            if *len >= max {
+                self.skip_line = true;
+                self.line += 1;
+                return Some(LineTooLong::new(max).into())
+            }
+        }
+        buf.push(c);
+        *len += 1;
+        None
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for ConfiguredLines<T, R> {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = String::with_capacity(self.options.capacity);
+        self.read_line(&mut buf).map(|r| r.map(|_| buf))
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over the lines of a reader along with their line numbers (reading utf-16)
+///
+/// Made by `LinesBuilder::build_numbered`. Lines are numbered from 1 like in
+/// `ConfiguredLines::line_number`, so a line that was too long still takes up its number.
+pub struct NumberedLines<T: ByteOrder, R>(ConfiguredLines<T, R>);
+
+impl<T: ByteOrder, R> NumberedLines<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.0.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.0.get_mut()
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.0.into_inner()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for NumberedLines<T, R> {
+    type Item = Result<(usize, String), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.0.next()?;
+        Some(line.map(|line| (self.0.line_number(), line)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Error};
+    use byteorder::LE;
+    use error::LineTooLong;
+    use read::{Utf16ReadExt, LineEnding, LineEndingKind, TrimMode};
+    use test_util::*;
+
+    fn too_long(e: &Error) -> LineTooLong {
+        *e.get_ref().unwrap().downcast_ref::<LineTooLong>().unwrap()
+    }
+
+    #[test]
+    fn lines_on_record_separator() {
+        let r = Cursor::new(le_str("a\u{1e}b\nc\r\u{1e}\u{1e}d"));
+        let records = r.utf16_lines_on::<LE>('\u{1e}');
+        assert_eq!(records.map(Result::unwrap).collect::<Vec<_>>(), ["a", "b\nc\r", "", "d"]);
+    }
+
+    #[test]
+    fn limited_lines_dont_count_the_cr_of_crlf() {
+        let lines = Cursor::new(le_str("abc\r\nxyz\n")).utf16_lines_limited::<LE>(3);
+        assert_eq!(lines.map(Result::unwrap).collect::<Vec<_>>(), ["abc", "xyz"]);
+    }
+
+    #[test]
+    fn limited_lines_skip_the_rest_of_a_long_line() {
+        let r = Cursor::new(le_str("abcdef\nxyz\nab\rc\r\nabc\r"));
+        let mut lines = r.utf16_lines_limited::<LE>(3);
+        assert_eq!(too_long(&lines.next().unwrap().unwrap_err()).max_len(), 3);
+        assert_eq!(lines.next().unwrap().unwrap(), "xyz");
+        assert_eq!(too_long(&lines.next().unwrap().unwrap_err()).max_len(), 3);
+        assert_eq!(too_long(&lines.next().unwrap().unwrap_err()).max_len(), 3);
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn limited_lines_count_consecutive_crs() {
+        let mut lines = Cursor::new(le_str("ab\r\r\nab\r\nb\r\r\n")).utf16_lines_builder::<LE>()
+            .max_len(2)
+            .build();
+        assert_eq!(too_long(&lines.next().unwrap().unwrap_err()).max_len(), 2);
+        assert_eq!(lines.next().unwrap().unwrap(), "ab");
+        assert_eq!(lines.next().unwrap().unwrap(), "b\r");
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn numbered_lines_count_lines_that_were_too_long() {
+        let r = Cursor::new(le_str("a\nbcde\r\n\nf"));
+        let mut lines = r.utf16_lines_builder::<LE>().max_len(3).build_numbered();
+        assert_eq!(lines.next().unwrap().unwrap(), (1, "a".to_owned()));
+        assert!(lines.next().unwrap().is_err());
+        assert_eq!(lines.next().unwrap().unwrap(), (3, "".to_owned()));
+        assert_eq!(lines.next().unwrap().unwrap(), (4, "f".to_owned()));
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn crlf_line_endings_keep_lone_lf_and_cr() {
+        let r = Cursor::new(le_str("a\nb\r\nc\rd\r\n"));
+        let lines = r.utf16_lines_builder::<LE>()
+            .line_endings(LineEndingKind::CrLf)
+            .keep_ends(true)
+            .build();
+        assert_eq!(lines.map(Result::unwrap).collect::<Vec<_>>(), ["a\nb\r\n", "c\rd\r\n"]);
+    }
+
+    #[test]
+    fn shortcut_iterators_share_the_line_splitting() {
+        let s = " a \r\nb\n c\r";
+        let styled = Cursor::new(le_str(s)).utf16_lines_with_style::<LE>();
+        assert_eq!(styled.map(Result::unwrap).collect::<Vec<_>>(), [
+            (" a ".to_owned(), LineEnding::CrLf),
+            ("b".to_owned(), LineEnding::Lf),
+            (" c\r".to_owned(), LineEnding::None),
+        ]);
+        let trimmed = Cursor::new(le_str(s)).utf16_lines_trimmed::<LE>(TrimMode::Both);
+        assert_eq!(trimmed.map(Result::unwrap).collect::<Vec<_>>(), ["a", "b", "c"]);
+        let mut reuse = Cursor::new(le_str(s)).utf16_lines_reuse::<LE>();
+        let mut buf = String::new();
+        let mut lines = Vec::new();
+        while let Some(r) = reuse.next_into(&mut buf) {
+            r.unwrap();
+            lines.push(buf.clone());
+        }
+        assert_eq!(lines, [" a ", "b", " c\r"]);
+    }
+}
@@ -0,0 +1,88 @@
+use std::io::{Read, Result, ErrorKind};
+
+#[derive(Debug)]
+/// A reader that yields a few bytes that were already read from the inner reader
+/// before continuing with the inner reader itself
+///
+/// This is used to put back bytes that were read while detecting a BOM.
+pub struct PrefixedReader<R> {
+    prefix: [u8; 4],
+    start: usize,
+    end: usize,
+    inner: R,
+}
+
+impl<R> PrefixedReader<R> {
+    /// Makes a new `PrefixedReader` that yields `prefix` before reading from `inner`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` is longer than 4 bytes
+    pub fn new(prefix: &[u8], inner: R) -> Self {
+        let mut buf = [0; 4];
+        buf[..prefix.len()].copy_from_slice(prefix);
+        PrefixedReader {
+            prefix: buf,
+            start: 0,
+            end: prefix.len(),
+            inner,
+        }
+    }
+    /// Returns the bytes of the prefix that haven't been read yet
+    pub fn prefix(&self) -> &[u8] {
+        &self.prefix[self.start..self.end]
+    }
+    /// Puts `bytes` back in front of the inner reader
+    ///
+    /// The old prefix must have been read already.
+    pub(crate) fn put_back(&mut self, bytes: &[u8]) {
+        debug_assert_eq!(self.start, self.end);
+        self.prefix[..bytes.len()].copy_from_slice(bytes);
+        self.start = 0;
+        self.end = bytes.len();
+    }
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Unwraps this reader, returning the underlying reader
+    ///
+    /// The bytes of the prefix that haven't been read yet are lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for PrefixedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.start < self.end {
+            let prefix = &self.prefix[self.start..self.end];
+            let n = ::std::cmp::min(prefix.len(), buf.len());
+            buf[..n].copy_from_slice(&prefix[..n]);
+            self.start += n;
+            Ok(n)
+        } else {
+            self.inner.read(buf)
+        }
+    }
+}
+
+/// Reads as many bytes as possible into `buf`, stopping early only at EOF
+///
+/// Returns how many bytes were read.
+pub(crate) fn read_up_to<R: Read + ?Sized>(r: &mut R, buf: &mut [u8]) -> Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match r.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
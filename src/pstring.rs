@@ -0,0 +1,100 @@
+use std::io::{Read, Write, Result, Error, ErrorKind};
+
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
+
+/// The integer type of the length prefix of a length-prefixed string
+///
+/// See `Utf16ReadExt::read_utf16_pstring` and `Utf16WriteExt::write_utf16_pstring`.
+pub trait LengthPrefix {
+    /// Reads a length prefix in endianness `T`
+    fn read_len<T: ByteOrder, R: Read + ?Sized>(r: &mut R) -> Result<usize>;
+    /// Writes a length prefix in endianness `T`
+    ///
+    /// Errors with `ErrorKind::InvalidInput` if `len` doesn't fit in the prefix.
+    fn write_len<T: ByteOrder, W: Write + ?Sized>(w: &mut W, len: usize) -> Result<()>;
+}
+
+fn too_long() -> Error {
+    Error::new(ErrorKind::InvalidInput, "string is too long for its length prefix")
+}
+
+impl LengthPrefix for u16 {
+    fn read_len<T: ByteOrder, R: Read + ?Sized>(r: &mut R) -> Result<usize> {
+        Ok(r.read_u16::<T>()? as usize)
+    }
+    fn write_len<T: ByteOrder, W: Write + ?Sized>(w: &mut W, len: usize) -> Result<()> {
+        if len > u16::MAX as usize {
+            return Err(too_long())
+        }
+        w.write_u16::<T>(len as u16)
+    }
+}
+
+impl LengthPrefix for u32 {
+    fn read_len<T: ByteOrder, R: Read + ?Sized>(r: &mut R) -> Result<usize> {
+        Ok(r.read_u32::<T>()? as usize)
+    }
+    fn write_len<T: ByteOrder, W: Write + ?Sized>(w: &mut W, len: usize) -> Result<()> {
+        if len > u32::MAX as usize {
+            return Err(too_long())
+        }
+        w.write_u32::<T>(len as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use byteorder::{LE, BE};
+    use read::Utf16ReadExt;
+    use write::Utf16WriteExt;
+
+    fn round_trip<L: LengthPrefix, T: ByteOrder>(s: &str) {
+        let mut w = Vec::new();
+        w.write_utf16_pstring::<L, T>(s).unwrap();
+        w.write_utf16_pstring::<L, T>("").unwrap();
+        let mut r = Cursor::new(w);
+        assert_eq!(r.read_utf16_pstring::<L, T>().unwrap(), s);
+        assert_eq!(r.read_utf16_pstring::<L, T>().unwrap(), "");
+        assert_eq!(r.position() as usize, r.get_ref().len());
+    }
+
+    #[test]
+    fn pstrings_round_trip() {
+        round_trip::<u16, LE>("a\u{1f600}b");
+        round_trip::<u16, BE>("a\u{1f600}b");
+        round_trip::<u32, LE>("a\u{1f600}b");
+        round_trip::<u32, BE>("a\u{1f600}b");
+    }
+
+    #[test]
+    fn zero_length_pstrings() {
+        let mut r = Cursor::new(vec![0, 0, 0x61, 0]);
+        assert_eq!(r.read_utf16_pstring::<u16, LE>().unwrap(), "");
+        assert_eq!(r.position(), 2);
+        let mut r = Cursor::new(vec![0, 0, 0, 0]);
+        assert_eq!(r.read_utf16_pstring::<u32, LE>().unwrap(), "");
+    }
+
+    #[test]
+    fn prefix_longer_than_the_input() {
+        let mut bytes = vec![3, 0];
+        bytes.extend_from_slice(&[0x61, 0, 0x62, 0]);
+        let e = Cursor::new(&bytes).read_utf16_pstring::<u16, LE>().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+        // A huge length isn't allocated up front
+        let mut bytes = vec![0xff, 0xff, 0xff, 0xff];
+        bytes.extend_from_slice(&[0x61, 0]);
+        let e = Cursor::new(&bytes).read_utf16_pstring::<u32, LE>().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn string_too_long_for_a_u16_prefix() {
+        let mut w = Vec::new();
+        let e = w.write_utf16_pstring::<u16, LE>(&"a".repeat(0x10000)).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert!(w.is_empty());
+    }
+}
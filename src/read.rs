@@ -1,30 +1,421 @@
-use std::io::{Read, Error, ErrorKind};
+use std::io::{Read, Seek, SeekFrom, Error, ErrorKind};
 
-use byteorder::{ByteOrder, ReadBytesExt};
+use byteorder::{ByteOrder, NativeEndian, ReadBytesExt, LE, BE};
+
+use buffered::BufShorts;
+use error::{Utf16Error, Utf16DecodeError, LineTooLong};
+use prefixed::read_up_to;
+use pstring::LengthPrefix;
+use seekable::SeekShorts;
+use sized::{SizedReader, SizedShorts, SizedChars};
+use lines::{LinesBuilder, ConfiguredLines, Ending};
+use transcode::Utf8Adapter;
+use typed::Utf16Reader;
 
 /// Extension to the `Read` trait
 pub trait Utf16ReadExt: ReadBytesExt {
+    /// Reads a short as its two raw bytes in the order they appear in the stream.
+    ///
+    /// The bytes are not reordered, so no endianness is needed. EOF is handled
+    /// like in `read_u16`.
+    fn read_short_bytes(&mut self) -> Result<[u8; 2], Error> {
+        let mut buf = [0; 2];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+    /// Like `Read::read` but with `u16`s
+    ///
+    /// Reads until `buf` is full or EOF is reached and returns the number of shorts read.
+    /// If the stream ends in the middle of a short, `ErrorKind::UnexpectedEof` is returned.
+    fn read_shorts<T: ByteOrder>(&mut self, buf: &mut [u16]) -> Result<usize, Error> {
+        let mut bytes = [0; 256];
+        let mut len = 0;
+        for chunk in buf.chunks_mut(bytes.len() / 2) {
+            let n = read_up_to(self, &mut bytes[..2 * chunk.len()])?;
+            if n % 2 == 1 {
+                return Err(odd_byte())
+            }
+            T::read_u16_into(&bytes[..n], &mut chunk[..n / 2]);
+            len += n / 2;
+            if n < 2 * chunk.len() {
+                break
+            }
+        }
+        Ok(len)
+    }
+    /// Like `Read::read_exact` but with `u16`s
+    ///
+    /// If EOF is reached before `buf` is full, `ErrorKind::UnexpectedEof` is returned
+    /// and the contents of `buf` are unspecified.
+    fn read_exact_shorts<T: ByteOrder>(&mut self, buf: &mut [u16]) -> Result<(), Error> {
+        let mut bytes = [0; 256];
+        for chunk in buf.chunks_mut(bytes.len() / 2) {
+            let bytes = &mut bytes[..2 * chunk.len()];
+            self.read_exact(bytes)?;
+            T::read_u16_into(bytes, chunk);
+        }
+        Ok(())
+    }
+    /// Seeks `n` shorts (2 bytes each) forwards, or backwards if `n` is negative,
+    /// from the current position
+    ///
+    /// Returns the new position in bytes like `Seek::seek`.
+    fn seek_shorts(&mut self, n: i64) -> Result<u64, Error>
+    where Self: Seek {
+        self.seek(SeekFrom::Current(2 * n))
+    }
+    /// Reads a string stored as its length in code units followed by that many code units
+    ///
+    /// `L` is the type of the length prefix (`u16` or `u32`), which is read in endianness `T`
+    /// like the string itself. If the stream ends before the whole string has been read,
+    /// `ErrorKind::UnexpectedEof` is returned. Malformed utf-16 is an error with
+    /// `ErrorKind::InvalidData`.
+    fn read_utf16_pstring<L: LengthPrefix, T: ByteOrder>(&mut self) -> Result<String, Error> {
+        let len = L::read_len::<T, _>(self)?;
+        // The length can't be trusted, so the shorts are read in chunks
+        // instead of allocating room for all of them up front
+        let mut shorts = Vec::new();
+        let mut chunk = [0; 1024];
+        while shorts.len() < len {
+            let chunk = &mut chunk[..::std::cmp::min(len - shorts.len(), 1024)];
+            self.read_exact_shorts::<T>(chunk)?;
+            shorts.extend_from_slice(chunk);
+        }
+        String::from_utf16(&shorts).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+    /// Wraps this instance in a `Utf8Adapter`, which reads the utf-16 of this reader as utf-8
+    fn into_utf8_reader<T: ByteOrder>(self) -> Utf8Adapter<T, Self>
+    where Self: Sized {
+        Utf8Adapter::new(self)
+    }
+    /// Wraps this instance in a `Utf16Reader` so the endianness doesn't have to be
+    /// given on every call.
+    fn into_utf16_reader<T: ByteOrder>(self) -> Utf16Reader<T, Self>
+    where Self: Sized {
+        Utf16Reader::new(self)
+    }
     /// Transforms this instance into an `Iterator` over its u16-units (shorts).
     ///
     /// The returned type implements `Iterator` where the `Item` is `Result<u16, R::Err>`.
     /// The yielded item is `Ok` if a short was successfully read and `Err` otherwise.
-    /// EOF is mapped to returning `None` from this iterator.
+    /// EOF is mapped to returning `None` from this iterator, unless the stream ends
+    /// on an odd byte, in which case `Err` with `ErrorKind::UnexpectedEof` is yielded first.
+    /// A reader returning only one byte per call is read again until the short is whole,
+    /// so pipes and sockets don't need to be buffered for this. Reads failing with
+    /// `ErrorKind::Interrupted` are retried. Any other error, like `ErrorKind::WouldBlock`,
+    /// is yielded as it is, losing a byte already read of that short, so use
+    /// `shorts_nonblocking` for non-blocking readers.
     fn shorts<T: ByteOrder>(self) -> Shorts<T, Self>
     where Self: Sized {
-        Shorts(PhantomData, self)
+        Shorts(PhantomData, self, None)
+    }
+    /// Like `shorts` but the returned iterator's `size_hint` is exact, since the reader
+    /// knows how many bytes it has left.
+    fn sized_shorts<T: ByteOrder>(self) -> SizedShorts<T, Self>
+    where Self: SizedReader + Sized {
+        SizedShorts::new(self)
+    }
+    /// Like `utf16_chars` but the returned iterator's `size_hint` is based on how many
+    /// bytes the reader has left.
+    ///
+    /// Since a `char` takes up one or two shorts, the hint can only be a range.
+    fn sized_chars<T: ByteOrder>(self) -> SizedChars<T, Self>
+    where Self: SizedReader + Sized {
+        SizedChars::new(self)
+    }
+    /// Like `shorts` but meant for non-blocking readers
+    ///
+    /// A read failing with `ErrorKind::WouldBlock` is yielded as that error, and the
+    /// iterator can be resumed with `next` once the reader is ready again. If it happened
+    /// in the middle of a short, the byte that was already read is kept, so the short is
+    /// completed by the next read instead of being lost like with `shorts`.
+    /// Interrupted reads are retried and EOF is handled like in `shorts`.
+    fn shorts_nonblocking<T: ByteOrder>(self) -> NonBlockingShorts<T, Self>
+    where Self: Sized {
+        NonBlockingShorts { inner: self, partial: None, _order: PhantomData }
+    }
+    /// Like `shorts` but borrows this reader instead of consuming it.
+    fn shorts_by_ref<T: ByteOrder>(&mut self) -> Shorts<T, &mut Self> {
+        Shorts(PhantomData, self, None)
+    }
+    /// Like `shorts` but the returned iterator can also be iterated from the back
+    ///
+    /// The end of the stream is found by seeking to it, so this can fail.
+    /// Iteration starts at the current position of the reader.
+    fn seekable_shorts<T: ByteOrder>(self) -> Result<SeekShorts<T, Self>, Error>
+    where Self: Seek + Sized {
+        SeekShorts::new(self)
+    }
+    /// Like `shorts` but the returned iterator can peek at the next short.
+    fn peekable_shorts<T: ByteOrder>(self) -> PeekableShorts<T, Self>
+    where Self: Sized {
+        PeekableShorts { inner: self.shorts(), peeked: None }
+    }
+    /// Like `shorts` but at most `n` shorts are read
+    ///
+    /// Nothing is read past the last short, so the reader returned by `into_inner`
+    /// continues right after it.
+    fn take_shorts<T: ByteOrder>(self, n: usize) -> TakeShorts<T, Self>
+    where Self: Sized {
+        TakeShorts { inner: self, remaining: n, _order: PhantomData }
+    }
+    /// Like `shorts` but stops after reading `sentinel`, which isn't yielded
+    ///
+    /// This is meant for null-terminated strings embedded in a larger stream.
+    /// Nothing is read past the sentinel, so the reader returned by `into_inner`
+    /// continues right after it.
+    fn shorts_until<T: ByteOrder>(self, sentinel: u16) -> ShortsUntil<T, Self>
+    where Self: Sized {
+        ShortsUntil { inner: self, sentinel, found: false, _order: PhantomData }
+    }
+    /// Like `utf16_chars` but at most `n` chars are read
+    ///
+    /// An error counts as one of the `n` items. Nothing is read past the last char,
+    /// so the reader returned by `into_inner` continues right after it.
+    fn take_chars<T: ByteOrder>(self, n: usize) -> TakeChars<T, Self>
+    where Self: Sized {
+        TakeChars { inner: self.utf16_chars(), remaining: n }
+    }
+    /// Like `shorts` but the bytes are read in chunks of up to `cap` bytes at a time.
+    ///
+    /// This avoids a call to `read` for every short, so wrapping the reader in a
+    /// `BufReader` isn't needed. A short split between two chunks is put back together.
+    fn buffered_shorts<T: ByteOrder>(self, cap: usize) -> BufShorts<T, Self>
+    where Self: Sized {
+        BufShorts::new(self, cap)
+    }
+    /// Transforms this instance into an `Iterator` over the runs of shorts
+    /// separated by `delim`, like `BufRead::split` but with `u16`s.
+    ///
+    /// The delimiter isn't included in the yielded runs. The last run is yielded
+    /// even if it isn't followed by `delim`, but when the stream ends right after
+    /// a delimiter, no empty run is yielded for it.
+    fn split_shorts<T: ByteOrder>(self, delim: u16) -> SplitShorts<T, Self>
+    where Self: Sized {
+        SplitShorts { inner: self, delim, _order: PhantomData }
     }
     /// Transforms this instance into an `Iterator` over `char`s from utf-16.
     ///
     /// The returned type implements `Iterator` where the `Item` is `Result<char, R::Err>`.
+    /// Like with `shorts`, a stream ending on an odd byte yields an `Err` before `None`
+    /// and reads failing with `ErrorKind::Interrupted` are retried, also in between
+    /// the two halves of a surrogate pair. When a high surrogate is followed by something
+    /// other than a low surrogate, an error is yielded for the high surrogate and decoding
+    /// continues with the short after it.
     fn utf16_chars<T: ByteOrder>(self) -> Chars<T, Self>
     where Self: Sized {
-        Chars(PhantomData, self)
+        Chars(PhantomData, self, DecodeState::default())
+    }
+    /// Like `utf16_chars` but errors are `Utf16Error`s, which tell I/O errors apart
+    /// from malformed utf-16.
+    ///
+    /// A surrogate that isn't part of a valid pair is yielded as `Utf16Error::Decode`,
+    /// and decoding continues with the short right after it. A stream ending on an odd
+    /// byte or in the middle of a surrogate pair is yielded as `Utf16Error::Io` with
+    /// `ErrorKind::UnexpectedEof`, like the error `utf16_chars` yields for it.
+    fn try_utf16_chars<T: ByteOrder>(self) -> TryChars<T, Self>
+    where Self: Sized {
+        TryChars { inner: self, state: DecodeState::default(), _order: PhantomData }
+    }
+    /// Like `utf16_chars` but drops the first char if it is a BOM (U+FEFF).
+    ///
+    /// A U+FEFF anywhere else is yielded like any other char.
+    fn utf16_chars_skip_bom<T: ByteOrder>(self) -> CharsSkipBom<T, Self>
+    where Self: Sized {
+        CharsSkipBom { inner: self.utf16_chars(), first: true }
+    }
+    /// Like `utf16_chars` but borrows this reader instead of consuming it.
+    ///
+    /// Once the iterator is dropped, the reader can be used again, continuing
+    /// right after the last `char` that was read.
+    fn utf16_chars_by_ref<T: ByteOrder>(&mut self) -> Chars<T, &mut Self> {
+        Chars(PhantomData, self, DecodeState::default())
+    }
+    /// Like `utf16_chars` but the returned iterator can peek at the next `char`.
+    ///
+    /// Peeking decodes the whole next `char`, including both halves of a surrogate pair,
+    /// so the following call to `next` yields exactly what was peeked.
+    fn utf16_chars_peekable<T: ByteOrder>(self) -> PeekableChars<T, Self>
+    where Self: Sized {
+        PeekableChars { inner: self.utf16_chars(), peeked: None }
+    }
+    /// Like `utf16_chars` but malformed utf-16 is replaced with U+FFFD (the replacement character)
+    ///
+    /// This mirrors `String::from_utf16_lossy`, so only errors from the reader itself are yielded.
+    fn utf16_chars_lossy<T: ByteOrder>(self) -> CharsLossy<T, Self>
+    where Self: Sized {
+        CharsLossy {
+            inner: self,
+            replacements: 0,
+            state: DecodeState::default(),
+            _order: PhantomData,
+        }
+    }
+    /// Like `utf16_chars` but Unicode non-characters are yielded as errors.
+    ///
+    /// The rejected code points are U+FDD0 to U+FDEF as well as the last two code points
+    /// of every plane (U+FFFE, U+FFFF, U+1FFFE, U+1FFFF, ... U+10FFFE, U+10FFFF).
+    /// They're yielded as `Err` with `ErrorKind::InvalidData`.
+    fn utf16_chars_strict<T: ByteOrder>(self) -> StrictChars<T, Self>
+    where Self: Sized {
+        StrictChars(PhantomData, self, DecodeState::default())
+    }
+    /// Transforms this instance into an `Iterator` over `char`s from utf-16
+    /// along with how many code units (1 or 2) each `char` was encoded with.
+    ///
+    /// The returned type implements `Iterator` where the `Item` is `Result<(char, u8), R::Err>`.
+    fn utf16_chars_with_width<T: ByteOrder>(self) -> CharsWithWidth<T, Self>
+    where Self: Sized {
+        CharsWithWidth {
+            inner: self,
+            last_width: 0,
+            state: DecodeState::default(),
+            _order: PhantomData,
+        }
+    }
+    /// Transforms this instance into an `Iterator` over `char`s from utf-16
+    /// along with the code units each `char` was read from.
+    ///
+    /// Every `char` has only one utf-16 encoding, so writing the code units gives back
+    /// the exact bytes that were read. They are kept in a `CodeUnits` without allocating.
+    fn utf16_chars_with_units<T: ByteOrder>(self) -> CharsWithUnits<T, Self>
+    where Self: Sized {
+        CharsWithUnits(self.utf16_chars())
+    }
+    /// Counts the `char`s this reader decodes to until EOF without keeping them
+    ///
+    /// The first error is returned, including malformed utf-16.
+    fn count_utf16_chars<T: ByteOrder>(self) -> Result<usize, Error>
+    where Self: Sized {
+        let mut count = 0;
+        for c in self.utf16_chars::<T>() {
+            c?;
+            count += 1;
+        }
+        Ok(count)
+    }
+    /// Counts the `char`s, code units and surrogate pairs of this reader until EOF
+    /// in a single pass
+    ///
+    /// Every surrogate pair is one char made of two code units, so `code_units` is
+    /// `chars + surrogate_pairs`. The first error is returned, including malformed utf-16.
+    fn utf16_stats<T: ByteOrder>(self) -> Result<Utf16Stats, Error>
+    where Self: Sized {
+        let mut stats = Utf16Stats::default();
+        for c in self.utf16_chars::<T>() {
+            let len = c?.len_utf16();
+            stats.chars += 1;
+            stats.code_units += len;
+            if len == 2 {
+                stats.surrogate_pairs += 1;
+            }
+        }
+        Ok(stats)
+    }
+    /// Counts the shorts of this reader until EOF without keeping them
+    ///
+    /// The first error is returned, including a trailing odd byte.
+    fn count_shorts<T: ByteOrder>(self) -> Result<usize, Error>
+    where Self: Sized {
+        let mut count = 0;
+        for short in self.shorts::<T>() {
+            short?;
+            count += 1;
+        }
+        Ok(count)
+    }
+    /// Checks whether the rest of this reader is well-formed utf-16 without decoding it
+    ///
+    /// Returns `Ok(false)` when there is an unpaired surrogate or a trailing odd byte,
+    /// stopping at the first one. Only I/O errors are returned as `Err`.
+    // Consumes the reader like `count_utf16_chars` does
+    #[allow(clippy::wrong_self_convention)]
+    fn is_valid_utf16<T: ByteOrder>(mut self) -> Result<bool, Error>
+    where Self: Sized {
+        // Whether the last short was a high surrogate waiting for its low surrogate
+        let mut high = false;
+        loop {
+            match read_short::<T, _>(&mut self) {
+                ReadShort::Short(u) => {
+                    let low = u & 0xfc00 == 0xdc00;
+                    if high != low {
+                        return Ok(false)
+                    }
+                    high = u & 0xfc00 == 0xd800;
+                }
+                ReadShort::Eof => return Ok(!high),
+                ReadShort::OddByte => return Ok(false),
+                ReadShort::Err(e) => return Err(e),
+            }
+        }
+    }
+    /// Transforms this instance into an `Iterator` over `char`s from utf-16 along with
+    /// the offset in code units where each `char` started.
+    ///
+    /// This is like `str::char_indices`, but the offsets count shorts instead of bytes,
+    /// so a `char` outside the BMP moves the offset forward by 2. Malformed utf-16 also moves
+    /// the offset forward by the number of code units that were consumed for it.
+    fn utf16_char_indices<T: ByteOrder>(self) -> CharIndices<T, Self>
+    where Self: Sized {
+        CharIndices { inner: self, offset: 0, state: DecodeState::default(), _order: PhantomData }
+    }
+    /// Transforms this instance into an `Iterator` over chunks of its `char`s whose utf-8
+    /// representation is at most `max_bytes` long
+    ///
+    /// A `char` is never split between two chunks, so a chunk is ended early when the next
+    /// `char` doesn't fit. A `char` longer than `max_bytes` on its own gets a chunk to itself.
+    /// If an error happens after some chars have been read, they are yielded first and the
+    /// error is yielded right after.
+    fn utf16_chunks<T: ByteOrder>(self, max_bytes: usize) -> Chunks<T, Self>
+    where Self: Sized {
+        Chunks { inner: self.utf16_chars(), max_bytes, pending: None }
+    }
+    /// Transforms this instance into an `Iterator` over batches of `n` of its `char`s
+    ///
+    /// Every batch has exactly `n` chars except the last one, which has the rest.
+    /// An error is yielded in place of the batch it happened in, and the chars read before it
+    /// are kept for the next batch, so the batches stay `n` chars long. An `n` of 0 is
+    /// rounded up to 1.
+    fn utf16_char_batches<T: ByteOrder>(self, n: usize) -> CharBatches<T, Self>
+    where Self: Sized {
+        let n = n.max(1);
+        let buf = Vec::with_capacity(n.min(MAX_BATCH_RESERVE));
+        CharBatches { inner: self.utf16_chars(), n, buf }
+    }
+    /// Returns an iterator over the words of this reader, like `str::split_whitespace`
+    ///
+    /// Words are separated by runs of whitespace (per `char::is_whitespace`), and whitespace
+    /// at the start or end of the stream doesn't give empty words. If an error happens in
+    /// the middle of a word, the error is yielded and the part of the word before it is dropped.
+    fn utf16_words<T: ByteOrder>(self) -> Words<T, Self>
+    where Self: Sized {
+        Words(self.utf16_chars())
     }
     /// Reads all chars (from utf16) until a newline is reached (U+000A) and
     /// appends them to the provided buffer.
+    ///
+    /// Returns the number of chars appended, including the newline. `Ok(0)` means the
+    /// stream had already ended, since an empty line still has its newline. A last line
+    /// without a newline returns the number of chars in it, and the next call returns `Ok(0)`.
+    ///
+    /// The buffer isn't cleared first, so room reserved in it beforehand is used.
+    /// Malformed utf-16 is returned as an error like in `read_utf16_until`.
     fn read_utf16_line<T: ByteOrder>(&mut self, buf: &mut String) -> Result<usize, Error> {
+        self.read_utf16_until::<T>('\n', buf)
+    }
+    /// Like `read_utf16_line` but the line may have at most `max_chars` chars,
+    /// not counting the newline or a `\r` right before it
+    ///
+    /// If the line is longer, an error with `ErrorKind::InvalidData` carrying a `LineTooLong`
+    /// is returned, so it can be told apart from I/O errors and malformed utf-16 by downcasting.
+    /// The reader is then left in the middle of the line, and `buf` holds what was read of it,
+    /// so nothing is lost. That is `max_chars + 1` chars, and a `\r` after them if there is one,
+    /// since a `\r` is only counted once the char after it has been read.
+    fn read_utf16_line_limited<T: ByteOrder>(&mut self, buf: &mut String, max_chars: usize)
+    -> Result<usize, Error> {
         let mut len = 0;
-        for c in self.utf16_chars::<T>() {
+        let mut chars = BorrowedChars::<T, _>::new(self);
+        for c in &mut chars {
             match c {
                 Ok(c) => {
                     buf.push(c);
@@ -32,6 +423,103 @@ pub trait Utf16ReadExt: ReadBytesExt {
                     if c == '\n' {
                         break
                     }
+                    let counted = if c == '\r' { len - 1 } else { len };
+                    if counted > max_chars {
+                        return Err(LineTooLong::new(max_chars).into())
+                    }
+                }
+                Err(e) => match e.kind() {
+                    ErrorKind::Interrupted => continue,
+                    _ => return Err(e),
+                }
+            }
+        }
+        chars.finish()?;
+        // A `\r` at the end of the stream isn't part of a line ending
+        if len > max_chars && buf.ends_with('\r') {
+            return Err(LineTooLong::new(max_chars).into())
+        }
+        Ok(len)
+    }
+    /// Reads all chars (from utf16) until `delim` is reached and appends them,
+    /// including `delim`, to the provided buffer.
+    ///
+    /// Returns the number of chars appended. `delim` can be any `char`, also one that needs
+    /// a surrogate pair, since it's compared against decoded chars. If the stream ends before
+    /// `delim` is found, everything up to the end is appended and `Ok` is returned,
+    /// so a return value of 0 means the stream had already ended.
+    ///
+    /// When a high surrogate isn't followed by a low surrogate, the short after it has
+    /// been read already, so it is decoded and appended before the error is returned.
+    /// That way, reading again continues right after it and nothing is lost.
+    fn read_utf16_until<T: ByteOrder>(&mut self, delim: char, buf: &mut String)
+    -> Result<usize, Error> {
+        let mut len = 0;
+        let mut chars = BorrowedChars::<T, _>::new(self);
+        for c in &mut chars {
+            match c {
+                Ok(c) => {
+                    buf.push(c);
+                    len += 1;
+                    if c == delim {
+                        break
+                    }
+                }
+                Err(e) => match e.kind() {
+                    ErrorKind::Interrupted => continue,
+                    _ => return Err(e),
+                }
+            }
+        }
+        chars.finish()?;
+        Ok(len)
+    }
+    /// Reads exactly `n` chars (from utf16) and appends them to the provided buffer.
+    ///
+    /// Chars outside the BMP take up two shorts but count as one char.
+    /// If the stream ends before `n` chars have been read, `ErrorKind::UnexpectedEof`
+    /// is returned. On error, `buf` is left as it was.
+    fn read_utf16_exact<T: ByteOrder>(&mut self, n: usize, buf: &mut String) -> Result<(), Error> {
+        let start = buf.len();
+        let mut chars = BorrowedChars::<T, _>::new(self);
+        for _ in 0..n {
+            match chars.next() {
+                Some(Ok(c)) => buf.push(c),
+                Some(Err(e)) => {
+                    buf.truncate(start);
+                    return Err(e)
+                }
+                None => {
+                    buf.truncate(start);
+                    let msg = "stream ended before all chars were read";
+                    return Err(Error::new(ErrorKind::UnexpectedEof, msg))
+                }
+            }
+        }
+        if let Err(e) = chars.finish() {
+            buf.truncate(start);
+            return Err(e)
+        }
+        Ok(())
+    }
+    /// Reads chars (from utf16) and appends them to the provided buffer for as long as
+    /// `pred` returns true.
+    ///
+    /// Returns the number of chars appended. Since the reader can't be rewound,
+    /// the first char for which `pred` returns false is consumed but not appended.
+    fn utf16_chars_while<T: ByteOrder, F>(&mut self, mut pred: F, buf: &mut String)
+    -> Result<usize, Error>
+    where F: FnMut(char) -> bool {
+        let mut len = 0;
+        let mut chars = BorrowedChars::<T, _>::new(self);
+        for c in &mut chars {
+            match c {
+                Ok(c) => {
+                    if !pred(c) {
+                        break
+                    }
+                    buf.push(c);
+                    len += 1;
                 }
                 Err(e) => match e.kind() {
                     ErrorKind::Interrupted => continue,
@@ -39,90 +527,1842 @@ pub trait Utf16ReadExt: ReadBytesExt {
                 }
             }
         }
+        chars.finish()?;
         Ok(len)
     }
+    /// Reads all chars (from utf16) until EOF and appends them to the provided buffer.
+    ///
+    /// Returns the number of chars appended. If an error occurs, the buffer is left
+    /// as it was before the call.
+    fn read_utf16_to_string<T: ByteOrder>(&mut self, buf: &mut String) -> Result<usize, Error> {
+        read_to_string::<T, _>(self, buf, false)
+    }
+    /// Reads all shorts until EOF and appends them to the provided buffer without decoding them.
+    ///
+    /// Returns the number of shorts appended. If an error occurs, the buffer is left
+    /// as it was before the call.
+    fn read_utf16_to_end<T: ByteOrder>(&mut self, buf: &mut Vec<u16>) -> Result<usize, Error> {
+        let start = buf.len();
+        for short in self.shorts::<T>() {
+            match short {
+                Ok(short) => buf.push(short),
+                Err(e) => {
+                    buf.truncate(start);
+                    return Err(e)
+                }
+            }
+        }
+        Ok(buf.len() - start)
+    }
+    /// Like `read_utf16_to_string` but drops the first char if it is a BOM (U+FEFF)
+    ///
+    /// Only a leading BOM is dropped, and it is not counted in the returned length.
+    fn read_utf16_to_string_skip_bom<T: ByteOrder>(&mut self, buf: &mut String)
+    -> Result<usize, Error> {
+        read_to_string::<T, _>(self, buf, true)
+    }
     /// Returns an iterator over the lines of this reader.
     ///
     /// Like the normal `BufRead::lines`, newlines characters aren't included
+    ///
+    /// Only `\n` ends a line, and a `\r` right before it is removed as part of the line ending.
+    /// A `\r` anywhere else, including a lone `\r` at the end of the stream, is kept in the line.
+    /// Use `utf16_lines_with` with `LineEndingKind::Any` to also end lines on a lone `\r`.
     fn utf16_lines<T: ByteOrder>(self) -> Lines<T, Self>
     where Self: Sized {
-        Lines(PhantomData, self)
+        Lines(self.utf16_lines_builder().build())
+    }
+    /// Like `utf16_lines` but the `String` of every line starts out with room for `cap` bytes
+    ///
+    /// This avoids growing the `String`s over and over when most lines have about the same length.
+    fn utf16_lines_with_capacity<T: ByteOrder>(self, cap: usize) -> Lines<T, Self>
+    where Self: Sized {
+        Lines(self.utf16_lines_builder().capacity(cap).build())
+    }
+    /// Like `utf16_lines` but every line is read into the same buffer to avoid allocating
+    ///
+    /// See `LinesReuse::next_into`.
+    fn utf16_lines_reuse<T: ByteOrder>(self) -> LinesReuse<T, Self>
+    where Self: Sized {
+        LinesReuse(self.utf16_lines_builder().build())
+    }
+    /// Returns an iterator over the lines of this reader along with how each line ended.
+    ///
+    /// The newline characters aren't included in the lines. A last line that
+    /// isn't terminated by a newline has the line ending `LineEnding::None`.
+    fn utf16_lines_with_style<T: ByteOrder>(self) -> LinesWithStyle<T, Self>
+    where Self: Sized {
+        LinesWithStyle(self.utf16_lines_builder().build())
+    }
+    /// Returns an iterator over the lines of this reader, ending lines as given by `recognize`
+    ///
+    /// If `keep_ending` is true, the line endings are kept at the end of the lines.
+    /// `utf16_lines` behaves like `LineEndingKind::Lf` except that a `\r` right before
+    /// the `\n` is removed as well.
+    fn utf16_lines_with<T: ByteOrder>(self, keep_ending: bool, recognize: LineEndingKind)
+    -> LinesWith<T, Self>
+    where Self: Sized {
+        LinesWith(self.utf16_lines_builder().line_endings(recognize).keep_ends(keep_ending).build())
+    }
+    /// Returns an iterator over the lines of this reader with whitespace trimmed
+    /// according to `mode`.
+    ///
+    /// Newlines are stripped like in `utf16_lines`, after which the line is trimmed
+    /// in place, so no extra allocation is made per line.
+    fn utf16_lines_trimmed<T: ByteOrder>(self, mode: TrimMode) -> TrimmedLines<T, Self>
+    where Self: Sized {
+        TrimmedLines(self.utf16_lines_builder().trim(mode).build())
+    }
+    /// Returns an iterator over the null-terminated records of this reader.
+    ///
+    /// Records are split on U+0000, which isn't included in the yielded records,
+    /// so a record can never contain a null char.
+    fn utf16_lines_nul<T: ByteOrder>(self) -> ConfiguredLines<T, Self>
+    where Self: Sized {
+        self.utf16_lines_on('\0')
+    }
+    /// Returns an iterator over the records of this reader separated by `sep`
+    ///
+    /// `sep` isn't included in the yielded records. Unless `sep` is U+000A, which makes this
+    /// the same as `utf16_lines`, a `\r` before it is kept like any other char.
+    /// This is a shorthand for `utf16_lines_builder` with only the delimiter set.
+    fn utf16_lines_on<T: ByteOrder>(self, sep: char) -> ConfiguredLines<T, Self>
+    where Self: Sized {
+        self.utf16_lines_builder().delimiter(sep).build()
+    }
+    /// Like `utf16_lines` but a line may have at most `max_chars` chars, not counting its ending
+    ///
+    /// A longer line is yielded as an error like in `read_utf16_line_limited`. This is a shorthand
+    /// for `utf16_lines_builder` with only the maximum length set.
+    fn utf16_lines_limited<T: ByteOrder>(self, max_chars: usize) -> ConfiguredLines<T, Self>
+    where Self: Sized {
+        self.utf16_lines_builder().max_len(max_chars).build()
+    }
+    /// Returns a builder for an iterator over the lines of this reader
+    /// with options for how lines are read.
+    ///
+    /// Building it without setting any options is the same as `utf16_lines`.
+    fn utf16_lines_builder<T: ByteOrder>(self) -> LinesBuilder<T, Self>
+    where Self: Sized {
+        LinesBuilder::new(self)
+    }
+    /// `read_u16` in the native endianness of the platform
+    fn read_u16_ne(&mut self) -> Result<u16, Error> {
+        self.read_u16::<NativeEndian>()
+    }
+    /// `read_u16` in little endian
+    fn read_u16_le(&mut self) -> Result<u16, Error> {
+        self.read_u16::<LE>()
+    }
+    /// `read_u16` in big endian
+    fn read_u16_be(&mut self) -> Result<u16, Error> {
+        self.read_u16::<BE>()
+    }
+    /// `shorts` in the native endianness of the platform
+    fn shorts_ne(self) -> Shorts<NativeEndian, Self>
+    where Self: Sized {
+        self.shorts()
+    }
+    /// `utf16_chars` in the native endianness of the platform
+    fn utf16_chars_ne(self) -> Chars<NativeEndian, Self>
+    where Self: Sized {
+        self.utf16_chars()
+    }
+    /// `read_utf16_line` in the native endianness of the platform
+    fn read_utf16_line_ne(&mut self, buf: &mut String) -> Result<usize, Error> {
+        self.read_utf16_line::<NativeEndian>(buf)
+    }
+    /// `utf16_lines` in the native endianness of the platform
+    fn utf16_lines_ne(self) -> Lines<NativeEndian, Self>
+    where Self: Sized {
+        self.utf16_lines()
     }
 }
 
 impl<T: Read> Utf16ReadExt for T {}
 
+fn read_to_string<T, R>(r: &mut R, buf: &mut String, mut skip_bom: bool) -> Result<usize, Error>
+where T: ByteOrder, R: Utf16ReadExt + ?Sized {
+    let start = buf.len();
+    let mut len = 0;
+    for c in r.utf16_chars::<T>() {
+        match c {
+            Ok('\u{feff}') if skip_bom => skip_bom = false,
+            Ok(c) => {
+                skip_bom = false;
+                buf.push(c);
+                len += 1;
+            }
+            Err(e) => match e.kind() {
+                ErrorKind::Interrupted => continue,
+                _ => {
+                    buf.truncate(start);
+                    return Err(e)
+                }
+            }
+        }
+    }
+    Ok(len)
+}
+
 use std::marker::PhantomData;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// An iterator over `u16` values of a reader.
-pub struct Shorts<T: ByteOrder, R>(PhantomData<T>, R);
-#[derive(Debug)]
+// The `Option` is a short that `Chars` read ahead before it was turned into this
+pub struct Shorts<T: ByteOrder, R>(PhantomData<T>, R, Option<u16>);
+#[derive(Debug, Clone)]
 /// An iterator over `char` values of a utf-16 reader.
-pub struct Chars<T: ByteOrder, R>(PhantomData<T>, R);
+///
+/// Malformed utf-16 is yielded as an error with `ErrorKind::InvalidData` carrying a
+/// `Utf16DecodeError`, which can be had with `get_ref` and `downcast_ref`. Its offset
+/// counts the shorts decoded by this iterator.
+pub struct Chars<T: ByteOrder, R>(PhantomData<T>, R, DecodeState);
 
-impl<T: ByteOrder, R: Utf16ReadExt> Iterator for Shorts<T, R> {
-    type Item = Result<u16, Error>;
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.1.read_u16::<T>() {
-                Ok(u) => break Some(Ok(u)),
-                Err(e) => match e.kind() {
-                    ErrorKind::Interrupted => (),
-                    ErrorKind::UnexpectedEof => break None,
-                    _ => break Some(Err(e)),
-                }
+/// `Shorts` in little endian
+pub type LittleShorts<R> = Shorts<LE, R>;
+/// `Shorts` in big endian
+pub type BigShorts<R> = Shorts<BE, R>;
+/// `Chars` in little endian
+pub type LittleChars<R> = Chars<LE, R>;
+/// `Chars` in big endian
+pub type BigChars<R> = Chars<BE, R>;
+/// `Lines` in little endian
+pub type LittleLines<R> = Lines<LE, R>;
+/// `Lines` in big endian
+pub type BigLines<R> = Lines<BE, R>;
+
+/// Implements `get_ref`, `get_mut` and `into_inner` for iterators wrapping a reader
+macro_rules! impl_inner {
+    ($($name:ident),*) => {$(
+        impl<T: ByteOrder, R> $name<T, R> {
+            /// Gets a reference to the underlying reader
+            pub fn get_ref(&self) -> &R {
+                &self.1
+            }
+            /// Gets a mutable reference to the underlying reader
+            pub fn get_mut(&mut self) -> &mut R {
+                &mut self.1
+            }
+            /// Unwraps this iterator, returning the underlying reader
+            pub fn into_inner(self) -> R {
+                self.1
             }
         }
-    }
+    )*};
 }
 
-use std::char::decode_utf16;
+impl_inner!(Shorts, Chars, StrictChars);
 
-impl<T: ByteOrder, R: Utf16ReadExt> Iterator for Chars<T, R> {
-    type Item = Result<char, Error>;
-    fn next(&mut self) -> Option<Self::Item> {
-        let first = match self.1.read_u16::<T>() {
-            Ok(f) => f,
-            Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => return None,
-            Err(e) => return Some(Err(e))
-        };
-        match decode_utf16(Some(first)).next().unwrap() {
-            Ok(c) => Some(Ok(c)),
-            Err(_) => {
-                let snd = match self.1.read_u16::<T>() {
-                    Ok(f) => f,
-                    Err(ref e) if e.kind() == ErrorKind::UnexpectedEof => return None,
-                    Err(e) => return Some(Err(e))
-                };
-                Some(decode_utf16(Some(first).into_iter().chain(Some(snd))).next().unwrap()
-                    .map_err(|e| Error::new(ErrorKind::InvalidData, e)))
+/// Implements `get_ref`, `get_mut`, `into_inner` and `map_inner` for line iterators
+/// wrapping a `ConfiguredLines`
+macro_rules! impl_lines_inner {
+    ($($name:ident),*) => {$(
+        impl<T: ByteOrder, R> $name<T, R> {
+            /// Gets a reference to the underlying reader
+            pub fn get_ref(&self) -> &R {
+                self.0.get_ref()
+            }
+            /// Gets a mutable reference to the underlying reader
+            pub fn get_mut(&mut self) -> &mut R {
+                self.0.get_mut()
+            }
+            /// Unwraps this iterator, returning the underlying reader
+            pub fn into_inner(self) -> R {
+                self.0.into_inner()
+            }
+            /// Replaces the underlying reader with what `f` returns for it
+            ///
+            /// This is useful for wrapping the reader in another one partway through.
+            pub fn map_inner<R2, F: FnOnce(R) -> R2>(self, f: F) -> $name<T, R2> {
+                $name(self.0.map_inner(f))
             }
         }
+    )*};
+}
+
+impl_lines_inner!(Lines, LinesReuse, LinesWithStyle);
+
+impl<T: ByteOrder, R> Shorts<T, R> {
+    /// Replaces the underlying reader with what `f` returns for it
+    ///
+    /// This is useful for wrapping the reader in another one partway through.
+    pub fn map_inner<R2, F: FnOnce(R) -> R2>(self, f: F) -> Shorts<T, R2> {
+        Shorts(PhantomData, f(self.1), self.2)
     }
 }
 
-#[derive(Debug)]
-/// An iterator over the lines of a reader (reading utf-16)
-pub struct Lines<T: ByteOrder, B>(PhantomData<T>, B);
+impl<T: ByteOrder, R> Chars<T, R> {
+    /// Replaces the underlying reader with what `f` returns for it
+    ///
+    /// This is useful for wrapping the reader in another one partway through.
+    pub fn map_inner<R2, F: FnOnce(R) -> R2>(self, f: F) -> Chars<T, R2> {
+        Chars(PhantomData, f(self.1), self.2)
+    }
+}
 
-impl<T: ByteOrder, B: Utf16ReadExt> Iterator for Lines<T, B> {
-    type Item = Result<String, Error>;
+impl<T: ByteOrder, R> StrictChars<T, R> {
+    /// Replaces the underlying reader with what `f` returns for it
+    ///
+    /// This is useful for wrapping the reader in another one partway through.
+    pub fn map_inner<R2, F: FnOnce(R) -> R2>(self, f: F) -> StrictChars<T, R2> {
+        StrictChars(PhantomData, f(self.1), self.2)
+    }
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut buf = String::new();
-        match self.1.read_utf16_line::<T>(&mut buf) {
-            Ok(0) => None,
-            Ok(_n) => {
-                if buf.ends_with("\n") {
-                    buf.pop();
-                    if buf.ends_with("\r") {
-                        buf.pop();
-                    }
-                }
-                Some(Ok(buf))
+macro_rules! impl_seek {
+    ($($name:ident),*) => {$(
+        /// Seeks the underlying reader
+        ///
+        /// Seeking to an odd byte offset makes every following short straddle two shorts
+        /// of the stream, see `Utf16ReadExt::seek_shorts` to seek in whole shorts.
+        impl<T: ByteOrder, R: Seek> Seek for $name<T, R> {
+            fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+                self.1.seek(pos)
             }
-            Err(e) => Some(Err(e))
         }
+    )*};
+}
+
+impl_seek!(Chars);
+
+/// Seeks the underlying reader
+///
+/// The next line starts where the reader was seeked to, see `ConfiguredLines`'s `Seek`.
+impl<T: ByteOrder, R: Seek> Seek for Lines<T, R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.0.seek(pos)
+    }
+}
+
+/// Seeks the underlying reader
+///
+/// A short kept from `Chars::into_shorts` is dropped, so the next short is read from
+/// where the reader was seeked to. Seeking to an odd byte offset makes every following
+/// short straddle two shorts of the stream, see `Utf16ReadExt::seek_shorts` to seek
+/// in whole shorts.
+impl<T: ByteOrder, R: Seek> Seek for Shorts<T, R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Error> {
+        self.2 = None;
+        self.1.seek(pos)
+    }
+}
+
+/// The outcome of trying to read a short
+pub(crate) enum ReadShort {
+    /// A whole short was read
+    Short(u16),
+    /// No bytes were left in the reader
+    Eof,
+    /// Only one byte was left in the reader
+    OddByte,
+    /// The reader returned an error
+    Err(Error),
+}
+
+/// Reads a short, telling apart a clean EOF from a stream ending on an odd byte
+///
+/// Short reads are continued like in `read_exact`, and `ErrorKind::Interrupted` is retried.
+pub(crate) fn read_short<T: ByteOrder, R: Read + ?Sized>(r: &mut R) -> ReadShort {
+    let mut buf = [0; 2];
+    let mut read = 0;
+    while read < 2 {
+        match r.read(&mut buf[read..]) {
+            Ok(0) if read == 0 => return ReadShort::Eof,
+            Ok(0) => return ReadShort::OddByte,
+            Ok(n) => read += n,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+            Err(e) => return ReadShort::Err(e),
+        }
+    }
+    ReadShort::Short(T::read_u16(&buf))
+}
+
+pub(crate) fn odd_byte() -> Error {
+    Error::new(ErrorKind::UnexpectedEof, "stream ended in the middle of a short")
+}
+
+fn next_short<T: ByteOrder, R: Read + ?Sized>(r: &mut R) -> Option<Result<u16, Error>> {
+    match read_short::<T, _>(r) {
+        ReadShort::Short(u) => Some(Ok(u)),
+        ReadShort::Eof => None,
+        ReadShort::OddByte => Some(Err(odd_byte())),
+        ReadShort::Err(e) => Some(Err(e)),
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for Shorts<T, R> {
+    type Item = Result<u16, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(short) = self.2.take() {
+            return Some(Ok(short))
+        }
+        next_short::<T, _>(&mut self.1)
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over `u16` values of a reader that can peek at the next short.
+pub struct PeekableShorts<T: ByteOrder, R> {
+    inner: Shorts<T, R>,
+    peeked: Option<Option<Result<u16, Error>>>,
+}
+
+impl<T: ByteOrder, R> PeekableShorts<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    ///
+    /// A peeked item that hasn't been consumed by `next` is lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> PeekableShorts<T, R> {
+    /// Returns a reference to the next item without consuming it
+    pub fn peek(&mut self) -> Option<&Result<u16, Error>> {
+        let inner = &mut self.inner;
+        self.peeked.get_or_insert_with(|| inner.next()).as_ref()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for PeekableShorts<T, R> {
+    type Item = Result<u16, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => self.inner.next(),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over `u16` values of a non-blocking reader
+///
+/// Unlike `Shorts`, a byte read right before `ErrorKind::WouldBlock` isn't lost.
+pub struct NonBlockingShorts<T: ByteOrder, R> {
+    inner: R,
+    partial: Option<u8>,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, R> NonBlockingShorts<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Returns the first byte of a short that was cut off by `ErrorKind::WouldBlock`, if any
+    pub fn partial_byte(&self) -> Option<u8> {
+        self.partial
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    ///
+    /// The byte of a short that was cut off by `ErrorKind::WouldBlock` is lost,
+    /// so check `partial_byte` first if it matters.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for NonBlockingShorts<T, R> {
+    type Item = Result<u16, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = [0; 2];
+        let mut read = 0;
+        if let Some(b) = self.partial.take() {
+            buf[0] = b;
+            read = 1;
+        }
+        while read < 2 {
+            match self.inner.read(&mut buf[read..]) {
+                Ok(0) if read == 0 => return None,
+                Ok(0) => return Some(Err(odd_byte())),
+                Ok(n) => read += n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+                Err(e) => {
+                    if read == 1 {
+                        self.partial = Some(buf[0]);
+                    }
+                    return Some(Err(e))
+                }
+            }
+        }
+        Some(Ok(T::read_u16(&buf)))
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over at most a given number of `u16` values of a reader
+pub struct TakeShorts<T: ByteOrder, R> {
+    inner: R,
+    remaining: usize,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, R> TakeShorts<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Unwraps this `TakeShorts`, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+    /// Returns how many more shorts can be yielded at most
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for TakeShorts<T, R> {
+    type Item = Result<u16, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None
+        }
+        self.remaining -= 1;
+        next_short::<T, _>(&mut self.inner)
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over the `u16` values of a reader up to a sentinel
+pub struct ShortsUntil<T: ByteOrder, R> {
+    inner: R,
+    sentinel: u16,
+    found: bool,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, R> ShortsUntil<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Unwraps this `ShortsUntil`, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+    /// Returns true if the sentinel has been read, as opposed to the reader having ended
+    pub fn found_sentinel(&self) -> bool {
+        self.found
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for ShortsUntil<T, R> {
+    type Item = Result<u16, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.found {
+            return None
+        }
+        match next_short::<T, _>(&mut self.inner) {
+            Some(Ok(short)) if short == self.sentinel => {
+                self.found = true;
+                None
+            }
+            item => item,
+        }
+    }
+}
+
+use std::char::REPLACEMENT_CHARACTER;
+
+/// The result of decoding one `char`
+pub(crate) struct Decoded {
+    /// The decoded `char` or the error that occured
+    pub result: Result<char, Error>,
+    /// How many code units were consumed
+    pub width: u8,
+    /// Whether the error came from malformed utf-16 rather than from the reader
+    pub malformed: bool,
+}
+
+impl Decoded {
+    fn new(result: Result<char, Error>, width: u8) -> Self {
+        Decoded { result, width, malformed: false }
+    }
+    fn malformed(e: Error, width: u8) -> Self {
+        Decoded { result: Err(e), width, malformed: true }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// The totals counted by `Utf16ReadExt::utf16_stats`
+pub struct Utf16Stats {
+    /// The number of `char`s
+    pub chars: usize,
+    /// The number of code units, which is the number of bytes divided by 2
+    pub code_units: usize,
+    /// The number of `char`s that were encoded as a surrogate pair
+    pub surrogate_pairs: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// What `decode_char` has to keep between calls
+pub(crate) struct DecodeState {
+    /// The number of code units decoded so far
+    pub offset: u64,
+    /// A short that was read after an unpaired high surrogate, which is decoded next
+    pending: Option<u16>,
+}
+
+/// Decodes the next `char`
+///
+/// When a high surrogate isn't followed by a low surrogate, the short after it is kept
+/// in `state` and decoded by the next call, so a valid `char` there isn't lost. Likewise,
+/// a high surrogate is kept if reading the short after it fails, so it can be paired up
+/// if reading is retried.
+pub(crate) fn decode_char<T, R>(r: &mut R, state: &mut DecodeState) -> Option<Decoded>
+where T: ByteOrder, R: Utf16ReadExt {
+    let offset = state.offset;
+    let first = match state.pending.take() {
+        Some(first) => first,
+        None => match read_short::<T, _>(r) {
+            ReadShort::Short(f) => f,
+            ReadShort::Eof => return None,
+            ReadShort::OddByte => return Some(Decoded::malformed(odd_byte(), 0)),
+            ReadShort::Err(e) => return Some(Decoded::new(Err(e), 0)),
+        }
+    };
+    let d = match first {
+        0xd800..=0xdbff => match read_short::<T, _>(r) {
+            ReadShort::Short(snd @ 0xdc00..=0xdfff) => {
+                let c = 0x10000 + ((first as u32 - 0xd800) << 10) + (snd as u32 - 0xdc00);
+                Decoded::new(Ok(::std::char::from_u32(c).unwrap()), 2)
+            }
+            ReadShort::Short(snd) => {
+                state.pending = Some(snd);
+                Decoded::malformed(Utf16DecodeError::new(first, offset).into(), 1)
+            }
+            ReadShort::Eof => {
+                let e = Error::new(ErrorKind::UnexpectedEof,
+                                   "stream ended in the middle of a surrogate pair");
+                Decoded::malformed(e, 1)
+            }
+            ReadShort::OddByte => Decoded::malformed(odd_byte(), 1),
+            ReadShort::Err(e) => {
+                state.pending = Some(first);
+                Decoded::new(Err(e), 0)
+            }
+        },
+        // A low surrogate can't start a pair, so there's no reason to read another short
+        0xdc00..=0xdfff => Decoded::malformed(Utf16DecodeError::new(first, offset).into(), 1),
+        _ => Decoded::new(Ok(::std::char::from_u32(first as u32).unwrap()), 1),
+    };
+    state.offset += d.width as u64;
+    Some(d)
+}
+
+impl<T: ByteOrder, R> Chars<T, R> {
+    /// Returns the short that was read after an unpaired high surrogate and is decoded next
+    pub(crate) fn pending_short(&self) -> Option<u16> {
+        self.2.pending
+    }
+    /// Turns this into an iterator over the shorts of the same reader
+    ///
+    /// The shorts continue right after the last `char` that was yielded. The only time
+    /// `Chars` reads ahead is when a high surrogate isn't followed by a low surrogate,
+    /// and the short after it is then the first short yielded, so nothing is lost.
+    pub fn into_shorts(self) -> Shorts<T, R> {
+        Shorts(PhantomData, self.1, self.2.pending)
+    }
+}
+
+/// Same as `Chars::into_shorts`
+impl<T: ByteOrder, R> From<Chars<T, R>> for Shorts<T, R> {
+    fn from(chars: Chars<T, R>) -> Self {
+        chars.into_shorts()
+    }
+}
+
+impl<T: ByteOrder, R> Shorts<T, R> {
+    /// Turns this into an iterator over the `char`s of the same reader
+    ///
+    /// `Shorts` never reads ahead, so the chars start right after the last short that was
+    /// yielded, and nothing is lost. If that was a high surrogate, the first char is an error.
+    /// Offsets in decode errors count from here.
+    pub fn into_chars(self) -> Chars<T, R> {
+        Chars(PhantomData, self.1, DecodeState { offset: 0, pending: self.2 })
+    }
+}
+
+/// Same as `Shorts::into_chars`
+impl<T: ByteOrder, R> From<Shorts<T, R>> for Chars<T, R> {
+    fn from(shorts: Shorts<T, R>) -> Self {
+        shorts.into_chars()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Chars<T, R> {
+    /// Reads the rest of the chars into a `String`, stopping at the first error
+    pub fn collect_string(self) -> Result<String, Error> {
+        self.collect()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for Chars<T, R> {
+    type Item = Result<char, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        decode_char::<T, _>(&mut self.1, &mut self.2).map(|d| d.result)
+    }
+}
+
+/// Decodes the chars of a borrowed reader for the methods of `Utf16ReadExt` that return
+/// on the first error
+///
+/// Such a method can't keep the short read after an unpaired high surrogate like `Chars`
+/// does, so the error is held back until that short has been decoded as well. If the method
+/// stops before the error has been yielded, `finish` returns it.
+struct BorrowedChars<'a, T: ByteOrder, R: ?Sized + 'a> {
+    inner: &'a mut R,
+    state: DecodeState,
+    error: Option<Error>,
+    _order: PhantomData<T>,
+}
+
+impl<'a, T: ByteOrder, R: Read + ?Sized> BorrowedChars<'a, T, R> {
+    fn new(inner: &'a mut R) -> Self {
+        BorrowedChars { inner, state: DecodeState::default(), error: None, _order: PhantomData }
+    }
+    fn finish(mut self) -> Result<(), Error> {
+        self.error.take().map_or(Ok(()), Err)
+    }
+}
+
+impl<'a, T: ByteOrder, R: Read + ?Sized> Iterator for BorrowedChars<'a, T, R> {
+    type Item = Result<char, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.state.pending.is_some() || self.error.is_none() {
+            let d = match decode_char::<T, _>(&mut self.inner, &mut self.state) {
+                Some(d) => d,
+                None => break,
+            };
+            match d.result {
+                Ok(c) => return Some(Ok(c)),
+                Err(e) if d.malformed && self.state.pending.is_some() => {
+                    self.error.get_or_insert(e);
+                }
+                Err(e) => return Some(Err(self.error.take().unwrap_or(e))),
+            }
+        }
+        self.error.take().map(Err)
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over `char` values of a utf-16 reader yielding `Utf16Error`s
+pub struct TryChars<T: ByteOrder, R> {
+    inner: R,
+    state: DecodeState,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, R> TryChars<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    ///
+    /// A short that was read after an unpaired high surrogate but not decoded yet is lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+    /// Returns how many shorts have been decoded so far
+    pub fn offset(&self) -> u64 {
+        self.state.offset
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for TryChars<T, R> {
+    type Item = Result<char, Utf16Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let d = decode_char::<T, _>(&mut self.inner, &mut self.state)?;
+        let malformed = d.malformed;
+        Some(d.result.map_err(|e| {
+            let decode = e.get_ref()
+                .and_then(|inner| inner.downcast_ref::<Utf16DecodeError>())
+                .filter(|_| malformed);
+            match decode {
+                Some(&decode) => Utf16Error::Decode(decode),
+                // The reader failed, or the stream ended in the middle of a short or a pair
+                None => Utf16Error::Io(e),
+            }
+        }))
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over `char` values of a utf-16 reader that drops a leading BOM
+pub struct CharsSkipBom<T: ByteOrder, R> {
+    inner: Chars<T, R>,
+    first: bool,
+}
+
+impl<T: ByteOrder, R> CharsSkipBom<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for CharsSkipBom<T, R> {
+    type Item = Result<char, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = self.inner.next();
+        if self.first {
+            self.first = false;
+            if let Some(Ok('\u{feff}')) = c {
+                return self.inner.next()
+            }
+        }
+        c
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over at most a given number of `char` values of a utf-16 reader
+pub struct TakeChars<T: ByteOrder, R> {
+    inner: Chars<T, R>,
+    remaining: usize,
+}
+
+impl<T: ByteOrder, R> TakeChars<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+    /// Unwraps this `TakeChars`, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+    /// Returns how many more chars can be yielded at most
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for TakeChars<T, R> {
+    type Item = Result<char, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None
+        }
+        self.remaining -= 1;
+        self.inner.next()
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over `char` values of a utf-16 reader that can peek at the next `char`.
+pub struct PeekableChars<T: ByteOrder, R> {
+    inner: Chars<T, R>,
+    peeked: Option<Option<Result<char, Error>>>,
+}
+
+impl<T: ByteOrder, R> PeekableChars<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    ///
+    /// A peeked item that hasn't been consumed by `next` is lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> PeekableChars<T, R> {
+    /// Returns a reference to the next item without consuming it
+    pub fn peek(&mut self) -> Option<&Result<char, Error>> {
+        let inner = &mut self.inner;
+        self.peeked.get_or_insert_with(|| inner.next()).as_ref()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for PeekableChars<T, R> {
+    type Item = Result<char, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => self.inner.next(),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over `char` values of a utf-16 reader replacing malformed utf-16.
+pub struct CharsLossy<T: ByteOrder, R> {
+    inner: R,
+    replacements: usize,
+    state: DecodeState,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, R> CharsLossy<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+    /// Replaces the underlying reader with what `f` returns for it
+    ///
+    /// This is useful for wrapping the reader in another one partway through.
+    /// The count of replacements is kept.
+    pub fn map_inner<R2, F: FnOnce(R) -> R2>(self, f: F) -> CharsLossy<T, R2> {
+        CharsLossy {
+            inner: f(self.inner),
+            replacements: self.replacements,
+            state: self.state,
+            _order: PhantomData,
+        }
+    }
+    /// Returns how many times malformed utf-16 has been replaced with U+FFFD so far
+    ///
+    /// This is the number of replacement characters yielded because of malformed utf-16,
+    /// so a U+FFFD that was in the stream itself isn't counted.
+    pub fn replacements(&self) -> usize {
+        self.replacements
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for CharsLossy<T, R> {
+    type Item = Result<char, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let d = decode_char::<T, _>(&mut self.inner, &mut self.state)?;
+        if d.malformed {
+            self.replacements += 1;
+            Some(Ok(REPLACEMENT_CHARACTER))
+        } else {
+            Some(d.result)
+        }
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over `char` values of a utf-16 reader that rejects non-characters.
+pub struct StrictChars<T: ByteOrder, R>(PhantomData<T>, R, DecodeState);
+
+fn is_noncharacter(c: char) -> bool {
+    let c = c as u32;
+    (0xfdd0..=0xfdef).contains(&c) || c & 0xfffe == 0xfffe
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for StrictChars<T, R> {
+    type Item = Result<char, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let d = decode_char::<T, _>(&mut self.1, &mut self.2)?;
+        Some(d.result.and_then(|c| if is_noncharacter(c) {
+            Err(Error::new(ErrorKind::InvalidData, format!("non-character U+{:04X}", c as u32)))
+        } else {
+            Ok(c)
+        }))
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over `char` values of a utf-16 reader along with
+/// the number of code units each `char` took up.
+pub struct CharsWithWidth<T: ByteOrder, R> {
+    inner: R,
+    last_width: u8,
+    state: DecodeState,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, R> CharsWithWidth<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+    /// Returns the number of code units consumed by the last item yielded
+    ///
+    /// Unlike the width in the yielded items, this is also set when an error was yielded.
+    pub fn last_width(&self) -> u8 {
+        self.last_width
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for CharsWithWidth<T, R> {
+    type Item = Result<(char, u8), Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let Decoded { result, width, .. } = decode_char::<T, _>(&mut self.inner, &mut self.state)?;
+        self.last_width = width;
+        Some(result.map(|c| (c, width)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The one or two code units a `char` is encoded with in utf-16
+pub struct CodeUnits {
+    units: [u16; 2],
+    len: u8,
+}
+
+impl CodeUnits {
+    /// Encodes `c` as utf-16
+    pub fn new(c: char) -> Self {
+        let mut units = [0; 2];
+        let len = c.encode_utf16(&mut units).len() as u8;
+        CodeUnits { units, len }
+    }
+    /// Returns the code units, which is a slice of length 1 or 2
+    pub fn as_slice(&self) -> &[u16] {
+        &self.units[..self.len as usize]
+    }
+}
+
+impl AsRef<[u16]> for CodeUnits {
+    fn as_ref(&self) -> &[u16] {
+        self.as_slice()
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over `char` values of a utf-16 reader along with
+/// the code units each `char` was read from.
+pub struct CharsWithUnits<T: ByteOrder, R>(Chars<T, R>);
+
+impl<T: ByteOrder, R> CharsWithUnits<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.0.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.0.get_mut()
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.0.into_inner()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for CharsWithUnits<T, R> {
+    type Item = Result<(char, CodeUnits), Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|c| c.map(|c| (c, CodeUnits::new(c))))
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over `char` values of a utf-16 reader along with
+/// the offset in code units where each `char` started.
+pub struct CharIndices<T: ByteOrder, R> {
+    inner: R,
+    offset: usize,
+    state: DecodeState,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, R> CharIndices<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+    /// Returns the offset in code units of the next item
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for CharIndices<T, R> {
+    type Item = Result<(usize, char), Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let Decoded { result, width, .. } = decode_char::<T, _>(&mut self.inner, &mut self.state)?;
+        let offset = self.offset;
+        self.offset += width as usize;
+        Some(result.map(|c| (offset, c)))
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over chunks of `char`s of a utf-16 reader with a limited utf-8 length
+pub struct Chunks<T: ByteOrder, R> {
+    inner: Chars<T, R>,
+    max_bytes: usize,
+    /// An item that was read but didn't go in the last chunk
+    pending: Option<Result<char, Error>>,
+}
+
+impl<T: ByteOrder, R> Chunks<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    ///
+    /// A `char` that was read but didn't fit in the last chunk is lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for Chunks<T, R> {
+    type Item = Result<String, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = String::new();
+        loop {
+            let c = match self.pending.take().or_else(|| self.inner.next()) {
+                Some(Ok(c)) => c,
+                Some(Err(e)) => {
+                    if buf.is_empty() {
+                        return Some(Err(e))
+                    }
+                    self.pending = Some(Err(e));
+                    return Some(Ok(buf))
+                }
+                None if buf.is_empty() => return None,
+                None => return Some(Ok(buf)),
+            };
+            if !buf.is_empty() && buf.len() + c.len_utf8() > self.max_bytes {
+                self.pending = Some(Ok(c));
+                return Some(Ok(buf))
+            }
+            buf.push(c);
+            if buf.len() >= self.max_bytes {
+                return Some(Ok(buf))
+            }
+        }
+    }
+}
+
+/// The most chars `CharBatches` reserves room for up front, so a huge `n` doesn't
+/// allocate (or overflow) before anything is read
+const MAX_BATCH_RESERVE: usize = 1024;
+
+#[derive(Debug)]
+/// An iterator over batches of a fixed number of `char`s of a utf-16 reader
+pub struct CharBatches<T: ByteOrder, R> {
+    inner: Chars<T, R>,
+    n: usize,
+    /// The chars of the batch being read, kept when an error interrupts it
+    buf: Vec<char>,
+}
+
+impl<T: ByteOrder, R> CharBatches<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    ///
+    /// The chars of a batch that was interrupted by an error are lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for CharBatches<T, R> {
+    type Item = Result<Vec<char>, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.buf.len() < self.n {
+            match self.inner.next() {
+                Some(Ok(c)) => self.buf.push(c),
+                Some(Err(e)) => return Some(Err(e)),
+                None => break,
+            }
+        }
+        if self.buf.is_empty() {
+            None
+        } else {
+            let next = Vec::with_capacity(self.n.min(MAX_BATCH_RESERVE));
+            Some(Ok(::std::mem::replace(&mut self.buf, next)))
+        }
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over the whitespace separated words of a reader (reading utf-16)
+pub struct Words<T: ByteOrder, R>(Chars<T, R>);
+
+impl<T: ByteOrder, R> Words<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.0.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.0.get_mut()
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.0.into_inner()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for Words<T, R> {
+    type Item = Result<String, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = String::new();
+        loop {
+            match self.0.next() {
+                Some(Ok(c)) if c.is_whitespace() => if !buf.is_empty() {
+                    return Some(Ok(buf))
+                },
+                Some(Ok(c)) => buf.push(c),
+                Some(Err(e)) => return Some(Err(e)),
+                None if buf.is_empty() => return None,
+                None => return Some(Ok(buf)),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An iterator over the lines of a reader (reading utf-16)
+pub struct Lines<T: ByteOrder, B>(ConfiguredLines<T, B>);
+
+impl<T: ByteOrder, B: Utf16ReadExt> Iterator for Lines<T, B> {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+#[derive(Debug)]
+/// Like `Lines` but each line is read into a buffer owned by the caller
+///
+/// This isn't an `Iterator`, since the lines borrow the buffer, but `next_into`
+/// can be called in a `while let` loop in the same way.
+pub struct LinesReuse<T: ByteOrder, B>(ConfiguredLines<T, B>);
+
+impl<T: ByteOrder, B: Utf16ReadExt> LinesReuse<T, B> {
+    /// Clears `buf` and reads the next line into it, without its line ending
+    ///
+    /// Returns `None` at EOF, like `Lines::next`.
+    pub fn next_into(&mut self, buf: &mut String) -> Option<Result<(), Error>> {
+        buf.clear();
+        self.0.read_line(buf).map(|r| r.map(|_| ()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How whitespace (per `char::is_whitespace`) should be trimmed from lines
+pub enum TrimMode {
+    /// Don't trim any whitespace
+    None,
+    /// Trim whitespace at the end of the line
+    TrailingWs,
+    /// Trim whitespace at both the start and the end of the line
+    Both,
+}
+
+impl TrimMode {
+    pub(crate) fn trim(self, buf: &mut String) {
+        match self {
+            TrimMode::None => (),
+            TrimMode::TrailingWs => {
+                let len = buf.trim_end().len();
+                buf.truncate(len);
+            }
+            TrimMode::Both => {
+                let len = buf.trim_end().len();
+                buf.truncate(len);
+                let start = len - buf.trim_start().len();
+                buf.drain(..start);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over the lines of a reader with whitespace trimmed (reading utf-16)
+pub struct TrimmedLines<T: ByteOrder, B>(ConfiguredLines<T, B>);
+
+impl<T: ByteOrder, B> TrimmedLines<T, B> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &B {
+        self.0.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut B {
+        self.0.get_mut()
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    pub fn into_inner(self) -> B {
+        self.0.into_inner()
+    }
+}
+
+impl<T: ByteOrder, B: Utf16ReadExt> Iterator for TrimmedLines<T, B> {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The newline sequence that ended a line
+pub enum LineEnding {
+    /// The line ended with `\n`
+    Lf,
+    /// The line ended with `\r\n`
+    CrLf,
+    /// The line wasn't terminated because the reader reached EOF
+    None,
+}
+
+#[derive(Debug)]
+/// An iterator over the lines of a reader and their line endings (reading utf-16)
+pub struct LinesWithStyle<T: ByteOrder, B>(ConfiguredLines<T, B>);
+
+impl<T: ByteOrder, B: Utf16ReadExt> Iterator for LinesWithStyle<T, B> {
+    type Item = Result<(String, LineEnding), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = String::new();
+        let ending = match self.0.read_line(&mut buf)? {
+            Ok(Ending::None) => LineEnding::None,
+            Ok(Ending::CrLf) => LineEnding::CrLf,
+            // Only `\n` ends a line, so there is no lone `\r`
+            Ok(Ending::Delimiter) | Ok(Ending::Cr) => LineEnding::Lf,
+            Err(e) => return Some(Err(e)),
+        };
+        Some(Ok((buf, ending)))
+    }
+}
+
+#[derive(Debug)]
+/// An iterator over the runs of shorts of a reader separated by a delimiter
+pub struct SplitShorts<T: ByteOrder, R> {
+    inner: R,
+    delim: u16,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, R> SplitShorts<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    /// Unwraps this `SplitShorts`, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Iterator for SplitShorts<T, R> {
+    type Item = Result<Vec<u16>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = Vec::new();
+        loop {
+            match next_short::<T, _>(&mut self.inner) {
+                Some(Ok(u)) if u == self.delim => return Some(Ok(buf)),
+                Some(Ok(u)) => buf.push(u),
+                Some(Err(e)) => return Some(Err(e)),
+                None if buf.is_empty() => return None,
+                None => return Some(Ok(buf)),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Which newline sequences end a line
+pub enum LineEndingKind {
+    /// Only `\n` ends a line
+    Lf,
+    /// Only `\r\n` ends a line, a lone `\n` or `\r` is part of the line
+    CrLf,
+    /// `\n`, `\r\n` and a lone `\r` all end a line
+    Any,
+}
+
+#[derive(Debug)]
+/// An iterator over the lines of a reader with configurable line endings (reading utf-16)
+pub struct LinesWith<T: ByteOrder, B>(ConfiguredLines<T, B>);
+
+impl<T: ByteOrder, B> LinesWith<T, B> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &B {
+        self.0.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut B {
+        self.0.get_mut()
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    ///
+    /// With `LineEndingKind::Any`, the char after a `\r` has to be read to see if it's
+    /// a `\n`, so that char is lost if it isn't.
+    pub fn into_inner(self) -> B {
+        self.0.into_inner()
+    }
+}
+
+impl<T: ByteOrder, B: Utf16ReadExt> Iterator for LinesWith<T, B> {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use byteorder::LE;
+    use counting::CountingReader;
+    use test_util::*;
+
+    #[test]
+    fn lone_high_surrogate_at_eof_is_an_error() {
+        let mut chars = Cursor::new(le(&[0xd800])).utf16_chars::<LE>();
+        let e = chars.next().unwrap().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+        assert!(chars.next().is_none());
+    }
+
+    #[test]
+    fn native_endian_methods_match_the_generic_ones() {
+        let bytes: Vec<u8> = "a\u{1f600}\nb\n".encode_utf16()
+            .flat_map(|u| u.to_ne_bytes().to_vec())
+            .collect();
+        assert_eq!(Cursor::new(&bytes).read_u16_ne().unwrap(),
+                   Cursor::new(&bytes).read_u16::<NativeEndian>().unwrap());
+        let ne: Vec<u16> = Cursor::new(&bytes).shorts_ne().map(Result::unwrap).collect();
+        let generic: Vec<u16> = Cursor::new(&bytes).shorts::<NativeEndian>()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(ne, generic);
+        let chars = Cursor::new(&bytes).utf16_chars_ne().collect_string().unwrap();
+        assert_eq!(chars, "a\u{1f600}\nb\n");
+        let (mut ne, mut generic) = (String::new(), String::new());
+        Cursor::new(&bytes).read_utf16_line_ne(&mut ne).unwrap();
+        Cursor::new(&bytes).read_utf16_line::<NativeEndian>(&mut generic).unwrap();
+        assert_eq!(ne, generic);
+        assert_eq!(Cursor::new(&bytes).utf16_lines_ne().map(Result::unwrap).collect::<Vec<_>>(),
+                   ["a\u{1f600}", "b"]);
+    }
+
+    fn decode_error(e: &Error) -> Utf16DecodeError {
+        *e.get_ref().unwrap().downcast_ref::<Utf16DecodeError>().unwrap()
+    }
+
+    #[test]
+    fn decode_errors_report_where_they_happened() {
+        // 'a', a surrogate pair, 'b', a lone low surrogate, a high surrogate before 'c'
+        let bytes = le(&[0x61, 0xd83d, 0xde00, 0x62, 0xdc00, 0xd800, 0x63]);
+        let mut chars = CountingReader::new(Cursor::new(bytes)).utf16_chars::<LE>();
+        assert_eq!(chars.next().unwrap().unwrap(), 'a');
+        assert_eq!(chars.next().unwrap().unwrap(), '\u{1f600}');
+        assert_eq!(chars.next().unwrap().unwrap(), 'b');
+        let e = decode_error(&chars.next().unwrap().unwrap_err());
+        assert_eq!((e.unpaired_surrogate(), e.offset()), (0xdc00, 4));
+        assert_eq!(chars.get_ref().shorts_read(), 5);
+        let e = decode_error(&chars.next().unwrap().unwrap_err());
+        assert_eq!((e.unpaired_surrogate(), e.offset()), (0xd800, 5));
+        // The short after the high surrogate had to be read to find the error
+        assert_eq!(chars.get_ref().shorts_read(), 7);
+        assert_eq!(chars.next().unwrap().unwrap(), 'c');
+        assert!(chars.next().is_none());
+    }
+
+    #[test]
+    fn take_shorts_leaves_the_rest_of_the_stream() {
+        let mut take = Cursor::new(le(&[1, 2, 3, 4, 5])).take_shorts::<LE>(3);
+        assert_eq!(take.by_ref().map(Result::unwrap).collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(take.remaining(), 0);
+        let mut rest = Vec::new();
+        take.into_inner().read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, le(&[4, 5]));
+    }
+
+    #[test]
+    fn cloned_chars_are_independent() {
+        let r = Cursor::new(le_str("ab\u{1f600}c"));
+        let mut chars: Chars<LE, Cursor<Vec<u8>>> = r.utf16_chars();
+        assert_eq!(chars.next().unwrap().unwrap(), 'a');
+        let clone = chars.clone();
+        assert_eq!(chars.collect_string().unwrap(), "b\u{1f600}c");
+        assert_eq!(clone.collect_string().unwrap(), "b\u{1f600}c");
+    }
+
+    #[test]
+    fn interrupted_read_inside_a_surrogate_pair_is_retried() {
+        let r = ScriptedReader::new(vec![Ok(le(&[0xd83d])), Err(interrupted()),
+                                         Ok(le(&[0xde00, 0x61]))]);
+        assert_eq!(r.utf16_chars::<LE>().collect_string().unwrap(), "\u{1f600}a");
+        let r = ScriptedReader::new(vec![Ok(vec![0x3d]), Err(interrupted()), Ok(vec![0xd8, 0x00]),
+                                         Err(interrupted()), Ok(vec![0xde])]);
+        assert_eq!(r.utf16_chars::<LE>().collect_string().unwrap(), "\u{1f600}");
+    }
+
+    #[test]
+    fn lone_low_surrogate_is_invalid_data() {
+        let mut chars = Cursor::new(le(&[0xdc00])).utf16_chars::<LE>();
+        let e = chars.next().unwrap().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert_eq!(decode_error(&e).unpaired_surrogate(), 0xdc00);
+        assert!(chars.next().is_none());
+    }
+
+    #[test]
+    fn chars_by_ref_can_be_interleaved_with_byte_reads() {
+        let mut bytes = le_str("a\u{1f600}");
+        bytes.push(0xff);
+        bytes.extend(le_str("b"));
+        let mut r = Cursor::new(bytes);
+        assert_eq!(r.utf16_chars_by_ref::<LE>().take(2).collect::<Result<String, _>>().unwrap(),
+                   "a\u{1f600}");
+        assert_eq!(r.read_u8().unwrap(), 0xff);
+        assert_eq!(r.utf16_chars_by_ref::<LE>().collect_string().unwrap(), "b");
+    }
+
+    #[test]
+    fn read_utf16_line_on_an_empty_reader() {
+        let mut buf = String::new();
+        assert_eq!(Cursor::new(Vec::new()).read_utf16_line::<LE>(&mut buf).unwrap(), 0);
+        assert!(buf.is_empty());
+        assert!(Cursor::new(Vec::new()).utf16_lines::<LE>().next().is_none());
+    }
+
+    #[test]
+    fn read_utf16_line_without_a_trailing_newline() {
+        let mut r = Cursor::new(le_str("a\nb\u{1f600}"));
+        let mut buf = String::new();
+        assert_eq!(r.read_utf16_line::<LE>(&mut buf).unwrap(), 2);
+        assert_eq!(r.read_utf16_line::<LE>(&mut buf).unwrap(), 2);
+        assert_eq!(buf, "a\nb\u{1f600}");
+        assert_eq!(r.read_utf16_line::<LE>(&mut buf).unwrap(), 0);
+        let lines = Cursor::new(le_str("a\nb\u{1f600}")).utf16_lines::<LE>();
+        assert_eq!(lines.map(Result::unwrap).collect::<Vec<_>>(), ["a", "b\u{1f600}"]);
+    }
+
+    #[test]
+    fn read_utf16_line_with_a_trailing_newline() {
+        let mut r = Cursor::new(le_str("a\n\n"));
+        let mut buf = String::new();
+        assert_eq!(r.read_utf16_line::<LE>(&mut buf).unwrap(), 2);
+        assert_eq!(r.read_utf16_line::<LE>(&mut buf).unwrap(), 1);
+        assert_eq!(r.read_utf16_line::<LE>(&mut buf).unwrap(), 0);
+        assert_eq!(buf, "a\n\n");
+        let lines = Cursor::new(le_str("a\n\n")).utf16_lines::<LE>();
+        assert_eq!(lines.map(Result::unwrap).collect::<Vec<_>>(), ["a", ""]);
+    }
+
+    #[test]
+    fn chars_skip_bom_drops_only_a_leading_bom() {
+        let with_bom = Cursor::new(le_str("\u{feff}a\u{feff}b")).utf16_chars_skip_bom::<LE>();
+        assert_eq!(with_bom.collect::<Result<String, _>>().unwrap(), "a\u{feff}b");
+        let without_bom = Cursor::new(le_str("a\u{feff}b")).utf16_chars_skip_bom::<LE>();
+        assert_eq!(without_bom.collect::<Result<String, _>>().unwrap(), "a\u{feff}b");
+    }
+
+    #[test]
+    fn chars_lossy_counts_replacements() {
+        // Two lone surrogates, a U+FFFD from the stream itself and a valid pair
+        // that is split between two reads
+        let first = le(&[0xdc00, 0x61, 0xd800, 0x62, 0xfffd, 0xd83d]);
+        let r = ScriptedReader::new(vec![Ok(first), Ok(le(&[0xde00]))]);
+        let mut chars = r.utf16_chars_lossy::<LE>();
+        let s = chars.by_ref().collect::<Result<String, _>>().unwrap();
+        assert_eq!(s, "\u{fffd}a\u{fffd}b\u{fffd}\u{1f600}");
+        assert_eq!(chars.replacements(), 2);
+    }
+
+    /// Lines with all four kinds of `\r` and `\n`: `\r\n`, `\n`, a bare `\r` and a `\r` mid-line
+    const MIXED_ENDINGS: &str = "a\r\nb\nc\rd\ne\rf";
+
+    #[test]
+    fn utf16_lines_only_ends_lines_on_lf() {
+        let lines = Cursor::new(le_str(MIXED_ENDINGS)).utf16_lines::<LE>();
+        assert_eq!(lines.map(Result::unwrap).collect::<Vec<_>>(), ["a", "b", "c\rd", "e\rf"]);
+    }
+
+    #[test]
+    fn utf16_lines_with_any_also_ends_lines_on_cr() {
+        let lines = |keep| {
+            Cursor::new(le_str(MIXED_ENDINGS)).utf16_lines_with::<LE>(keep, LineEndingKind::Any)
+        };
+        assert_eq!(lines(false).map(Result::unwrap).collect::<Vec<_>>(),
+                   ["a", "b", "c", "d", "e", "f"]);
+        assert_eq!(lines(true).map(Result::unwrap).collect::<Vec<_>>(),
+                   ["a\r\n", "b\n", "c\r", "d\n", "e\r", "f"]);
+    }
+
+    #[test]
+    fn read_u16_le_and_be_byte_order() {
+        let mut r = Cursor::new([0x12, 0x34, 0x12, 0x34]);
+        assert_eq!(r.read_u16_le().unwrap(), 0x3412);
+        assert_eq!(r.read_u16_be().unwrap(), 0x1234);
+    }
+
+    #[test]
+    fn into_shorts_keeps_the_short_after_an_unpaired_high_surrogate() {
+        let mut chars = Cursor::new(le(&[0xd800, 0x41, 0x42])).utf16_chars::<LE>();
+        assert!(chars.next().unwrap().is_err());
+        let shorts = chars.clone().into_shorts();
+        assert_eq!(shorts.map(Result::unwrap).collect::<Vec<_>>(), [0x41, 0x42]);
+        let shorts: Shorts<_, _> = chars.into();
+        assert_eq!(shorts.into_chars().collect_string().unwrap(), "AB");
+    }
+
+    #[test]
+    fn read_utf16_line_keeps_the_short_after_an_unpaired_high_surrogate() {
+        let mut r = Cursor::new(le(&[0xd800, 0x61, 0x62, 0x0a]));
+        let mut buf = String::new();
+        let e = r.read_utf16_line::<LE>(&mut buf).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert_eq!(buf, "a");
+        assert_eq!(r.read_utf16_line::<LE>(&mut buf).unwrap(), 2);
+        assert_eq!(buf, "ab\n");
+
+        let mut lines = Cursor::new(le(&[0xd800, 0x61, 0x62, 0x0a])).utf16_lines::<LE>();
+        assert!(lines.next().unwrap().is_err());
+        assert_eq!(lines.next().unwrap().unwrap(), "ab");
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn read_utf16_until_returns_the_error_after_a_delimiter_read_ahead() {
+        let mut r = Cursor::new(le(&[0xd800, 0x0a, 0x62]));
+        let mut buf = String::new();
+        assert!(r.read_utf16_line::<LE>(&mut buf).is_err());
+        assert_eq!(buf, "\n");
+        buf.clear();
+        assert_eq!(r.read_utf16_line::<LE>(&mut buf).unwrap(), 1);
+        assert_eq!(buf, "b");
+    }
+
+    #[test]
+    fn try_chars_tell_io_errors_from_decode_errors_like_chars() {
+        let bytes = le(&[0x61, 0xdc00, 0xd800, 0x62, 0xd800]);
+        let mut chars = Cursor::new(&bytes).try_utf16_chars::<LE>();
+        assert_eq!(chars.next().unwrap().unwrap(), 'a');
+        match chars.next().unwrap() {
+            Err(Utf16Error::Decode(e)) => assert_eq!(e, Utf16DecodeError::new(0xdc00, 1)),
+            r => panic!("expected a decode error, got {:?}", r),
+        }
+        match chars.next().unwrap() {
+            Err(Utf16Error::Decode(e)) => assert_eq!(e, Utf16DecodeError::new(0xd800, 2)),
+            r => panic!("expected a decode error, got {:?}", r),
+        }
+        assert_eq!(chars.next().unwrap().unwrap(), 'b');
+        match chars.next().unwrap() {
+            Err(Utf16Error::Io(e)) => assert_eq!(e.kind(), ErrorKind::UnexpectedEof),
+            r => panic!("expected an I/O error, got {:?}", r),
+        }
+        assert!(chars.next().is_none());
+        assert_eq!(chars.offset(), 5);
+        let e = Cursor::new(&bytes).utf16_chars::<LE>().nth(4).unwrap().unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn read_utf16_exact_counts_chars_not_shorts() {
+        let mut r = Cursor::new(le_str("a\u{1f600}bc"));
+        let mut buf = "x".to_owned();
+        r.read_utf16_exact::<LE>(3, &mut buf).unwrap();
+        assert_eq!(buf, "xa\u{1f600}b");
+        assert_eq!(r.position(), 8);
+        let e = r.read_utf16_exact::<LE>(2, &mut buf).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(buf, "xa\u{1f600}b");
+    }
+
+    #[test]
+    fn read_utf16_line_limited_doesnt_count_the_cr_of_crlf() {
+        let mut r = Cursor::new(le_str("abc\r\nxyz\n"));
+        let mut buf = String::new();
+        assert_eq!(r.read_utf16_line_limited::<LE>(&mut buf, 3).unwrap(), 5);
+        assert_eq!(buf, "abc\r\n");
+        buf.clear();
+        assert_eq!(r.read_utf16_line_limited::<LE>(&mut buf, 3).unwrap(), 4);
+        assert_eq!(buf, "xyz\n");
+    }
+
+    #[test]
+    fn read_utf16_line_limited_counts_any_other_cr() {
+        for s in &["ab\rc\n", "abc\r"] {
+            let mut buf = String::new();
+            let e = Cursor::new(le_str(s)).read_utf16_line_limited::<LE>(&mut buf, 3).unwrap_err();
+            assert!(e.get_ref().unwrap().is::<LineTooLong>());
+            assert_eq!(buf, s.trim_end_matches('\n'));
+        }
+    }
+
+    #[test]
+    fn shorts_until_reads_a_null_terminated_field() {
+        // A short, the null-terminated string "hi" and another short
+        let mut r = Cursor::new(le(&[7, 0x68, 0x69, 0, 9]));
+        assert_eq!(r.read_u16::<LE>().unwrap(), 7);
+        let mut field = (&mut r).shorts_until::<LE>(0);
+        let units = field.by_ref().collect::<Result<Vec<_>, _>>().unwrap();
+        assert!(field.found_sentinel());
+        assert_eq!(String::from_utf16(&units).unwrap(), "hi");
+        assert_eq!(r.read_u16::<LE>().unwrap(), 9);
+    }
+
+    #[test]
+    fn char_batches_of_usize_max() {
+        let mut batches = Cursor::new(le_str("abc")).utf16_char_batches::<LE>(usize::MAX);
+        assert_eq!(batches.next().unwrap().unwrap(), ['a', 'b', 'c']);
+        assert!(batches.next().is_none());
+    }
+
+    #[test]
+    fn char_batches_count_chars_not_shorts() {
+        let bytes = le_str("a\u{1f600}bcd");
+        let batches = Cursor::new(&bytes).utf16_char_batches::<LE>(2);
+        let batches = batches.map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(batches, [vec!['a', '\u{1f600}'], vec!['b', 'c'], vec!['d']]);
+
+        let batches = Cursor::new(&bytes).utf16_char_batches::<LE>(1);
+        assert_eq!(batches.map(Result::unwrap).collect::<Vec<_>>().len(), 5);
+
+        let batches = Cursor::new(&bytes).utf16_char_batches::<LE>(0);
+        assert_eq!(batches.map(Result::unwrap).next().unwrap(), ['a']);
+    }
+
+    #[test]
+    fn shorts_and_chars_from_one_byte_reads() {
+        let bytes = le(&[0x61, 0xd83d, 0xde00]);
+        let shorts = ScriptedReader::one_byte(&bytes).shorts::<LE>();
+        assert_eq!(shorts.map(Result::unwrap).collect::<Vec<_>>(), [0x61, 0xd83d, 0xde00]);
+        let chars = ScriptedReader::one_byte(&bytes).utf16_chars::<LE>();
+        assert_eq!(chars.collect_string().unwrap(), "a\u{1f600}");
+    }
+
+    #[test]
+    fn odd_byte_from_one_byte_reads() {
+        let mut shorts = ScriptedReader::one_byte(&[0x61, 0, 0x62]).shorts::<LE>();
+        assert_eq!(shorts.next().unwrap().unwrap(), 0x61);
+        assert_eq!(shorts.next().unwrap().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert!(shorts.next().is_none());
+        let mut chars = ScriptedReader::one_byte(&[0x61, 0, 0x62]).utf16_chars::<LE>();
+        assert_eq!(chars.next().unwrap().unwrap(), 'a');
+        assert_eq!(chars.next().unwrap().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert!(chars.next().is_none());
+    }
+
+    #[test]
+    fn peeking_an_astral_char_reads_the_whole_pair() {
+        let bytes = le(&[0xd83d, 0xde00, 0x61, 0xdc00]);
+        let mut chars = Cursor::new(bytes).utf16_chars_peekable::<LE>();
+        assert_eq!(chars.peek().unwrap().as_ref().unwrap(), &'\u{1f600}');
+        assert_eq!(chars.get_ref().position(), 4);
+        assert_eq!(chars.peek().unwrap().as_ref().unwrap(), &'\u{1f600}');
+        assert_eq!(chars.next().unwrap().unwrap(), '\u{1f600}');
+        assert_eq!(chars.peek().unwrap().as_ref().unwrap(), &'a');
+        assert_eq!(chars.next().unwrap().unwrap(), 'a');
+        assert_eq!(chars.peek().unwrap().as_ref().unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(chars.next().unwrap().unwrap_err().kind(), ErrorKind::InvalidData);
+        assert!(chars.peek().is_none());
+        assert!(chars.next().is_none());
+    }
+
+    fn words(s: &str) -> Vec<String> {
+        Cursor::new(le_str(s)).utf16_words::<LE>().map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn words_skip_whitespace_at_both_ends() {
+        assert_eq!(words("  one two\n"), ["one", "two"]);
+        assert_eq!(words("\tone"), ["one"]);
+    }
+
+    #[test]
+    fn words_split_on_runs_of_mixed_whitespace() {
+        assert_eq!(words("a \t\r\n\u{3000}b\u{a0}\u{1f600}"), ["a", "b", "\u{1f600}"]);
+    }
+
+    #[test]
+    fn words_of_an_empty_or_blank_stream() {
+        assert!(words("").is_empty());
+        assert!(words(" \n\t ").is_empty());
+    }
+
+    #[test]
+    fn words_yield_an_error_in_place_of_the_broken_word() {
+        let mut bytes = le_str("ab c");
+        bytes.extend(le(&[0xdc00]));
+        bytes.extend(le_str("d e"));
+        let mut words = Cursor::new(bytes).utf16_words::<LE>();
+        assert_eq!(words.next().unwrap().unwrap(), "ab");
+        assert_eq!(words.next().unwrap().unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(words.next().unwrap().unwrap(), "d");
+        assert_eq!(words.next().unwrap().unwrap(), "e");
+        assert!(words.next().is_none());
     }
 }
@@ -0,0 +1,159 @@
+use std::io::{Read, Seek, SeekFrom, Error};
+use std::marker::PhantomData;
+
+use byteorder::ByteOrder;
+
+use read::odd_byte;
+
+#[derive(Debug)]
+/// An iterator over `u16` values of a seekable reader that can also be iterated from the back
+///
+/// This is made with `seekable_shorts`, which finds the end of the stream up front.
+/// Items from the front and from the back are read by seeking to them, so the two ends
+/// can be mixed freely and meet in the middle.
+pub struct SeekShorts<T: ByteOrder, R> {
+    inner: R,
+    // Start of the first and end of the last short that haven't been yielded
+    front: u64,
+    back: u64,
+    // Whether the stream has an odd byte after `back` that hasn't been reported yet
+    odd: bool,
+    // Where the inner reader is now, if known
+    pos: Option<u64>,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, R: Read + Seek> SeekShorts<T, R> {
+    pub(crate) fn new(mut inner: R) -> Result<Self, Error> {
+        let front = inner.stream_position()?;
+        let end = inner.seek(SeekFrom::End(0))?;
+        let len = end.saturating_sub(front);
+        Ok(SeekShorts {
+            inner,
+            front,
+            back: front + len / 2 * 2,
+            odd: len % 2 == 1,
+            pos: Some(end),
+            _order: PhantomData,
+        })
+    }
+    fn read_at(&mut self, at: u64) -> Result<u16, Error> {
+        if self.pos != Some(at) {
+            self.pos = None;
+            self.inner.seek(SeekFrom::Start(at))?;
+        }
+        let mut buf = [0; 2];
+        match self.inner.read_exact(&mut buf) {
+            Ok(()) => {
+                self.pos = Some(at + 2);
+                Ok(T::read_u16(&buf))
+            }
+            Err(e) => {
+                self.pos = None;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl<T: ByteOrder, R> SeekShorts<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying reader
+    ///
+    /// Seeking it doesn't change which shorts are yielded next.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.pos = None;
+        &mut self.inner
+    }
+    /// Unwraps this iterator, returning the underlying reader
+    ///
+    /// The position of the reader is wherever the last short was read from.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<T: ByteOrder, R: Read + Seek> Iterator for SeekShorts<T, R> {
+    type Item = Result<u16, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front < self.back {
+            let at = self.front;
+            self.front += 2;
+            Some(self.read_at(at))
+        } else if self.odd {
+            self.odd = false;
+            Some(Err(odd_byte()))
+        } else {
+            None
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = ((self.back - self.front) / 2) as usize + self.odd as usize;
+        (n, Some(n))
+    }
+}
+
+/// A trailing odd byte is yielded as an error first when iterating from the back
+impl<T: ByteOrder, R: Read + Seek> DoubleEndedIterator for SeekShorts<T, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.odd {
+            self.odd = false;
+            Some(Err(odd_byte()))
+        } else if self.front < self.back {
+            self.back -= 2;
+            let at = self.back;
+            Some(self.read_at(at))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, ErrorKind};
+    use byteorder::LE;
+    use read::Utf16ReadExt;
+    use test_util::*;
+
+    #[test]
+    fn front_and_back_meet_in_the_middle() {
+        let mut shorts = Cursor::new(le(&[1, 2, 3, 4, 5])).seekable_shorts::<LE>().unwrap();
+        assert_eq!(shorts.size_hint(), (5, Some(5)));
+        assert_eq!(shorts.next().unwrap().unwrap(), 1);
+        assert_eq!(shorts.next_back().unwrap().unwrap(), 5);
+        assert_eq!(shorts.next_back().unwrap().unwrap(), 4);
+        assert_eq!(shorts.next().unwrap().unwrap(), 2);
+        assert_eq!(shorts.size_hint(), (1, Some(1)));
+        assert_eq!(shorts.next_back().unwrap().unwrap(), 3);
+        assert!(shorts.next().is_none());
+        assert!(shorts.next_back().is_none());
+    }
+
+    #[test]
+    fn starts_at_the_current_position() {
+        let mut r = Cursor::new(le(&[1, 2, 3, 4]));
+        r.set_position(4);
+        let shorts = r.seekable_shorts::<LE>().unwrap();
+        assert_eq!(shorts.rev().map(Result::unwrap).collect::<Vec<_>>(), [4, 3]);
+    }
+
+    #[test]
+    fn odd_byte_is_reported_once_from_either_end() {
+        let mut bytes = le(&[1, 2]);
+        bytes.push(3);
+        let mut shorts = Cursor::new(&bytes).seekable_shorts::<LE>().unwrap();
+        assert_eq!(shorts.size_hint(), (3, Some(3)));
+        assert_eq!(shorts.next_back().unwrap().unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(shorts.next_back().unwrap().unwrap(), 2);
+        assert_eq!(shorts.next().unwrap().unwrap(), 1);
+        assert!(shorts.next().is_none());
+
+        let shorts = Cursor::new(&bytes).seekable_shorts::<LE>().unwrap();
+        let items = shorts.map(|r| r.map_err(|e| e.kind())).collect::<Vec<_>>();
+        assert_eq!(items, [Ok(1), Ok(2), Err(ErrorKind::UnexpectedEof)]);
+    }
+}
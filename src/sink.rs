@@ -0,0 +1,79 @@
+use std::io::{Write, Result, Error};
+use std::marker::PhantomData;
+
+use byteorder::{ByteOrder, WriteBytesExt};
+
+#[derive(Debug)]
+/// A writer implementing `Extend` that writes utf-16 in endianness `T` to the inner writer
+///
+/// It can be extended with `u16`s, which are written as they are, or with `char`s, which are
+/// encoded first. Since `Extend` can't return the I/O error, the first one is stored and can be
+/// retrieved with `into_result`. Once an error has happened, nothing more is written.
+pub struct Utf16Sink<T: ByteOrder, W> {
+    inner: W,
+    error: Option<Error>,
+    _order: PhantomData<T>,
+}
+
+impl<T: ByteOrder, W: Write> Utf16Sink<T, W> {
+    /// Makes a new `Utf16Sink` writing to `inner`
+    pub fn new(inner: W) -> Self {
+        Utf16Sink {
+            inner,
+            error: None,
+            _order: PhantomData,
+        }
+    }
+    /// Gets a reference to the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+    /// Gets a mutable reference to the underlying writer
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+    /// Returns the stored I/O error, if any
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+    /// Unwraps this `Utf16Sink`, returning the underlying writer,
+    /// or the first I/O error that happened while writing
+    pub fn into_result(self) -> Result<W> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.inner),
+        }
+    }
+    fn write_short(&mut self, short: u16) {
+        if self.error.is_none() {
+            if let Err(e) = self.inner.write_u16::<T>(short) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+impl<T: ByteOrder, W: Write> Extend<u16> for Utf16Sink<T, W> {
+    fn extend<I: IntoIterator<Item = u16>>(&mut self, iter: I) {
+        for short in iter {
+            if self.error.is_some() {
+                break
+            }
+            self.write_short(short);
+        }
+    }
+}
+
+impl<T: ByteOrder, W: Write> Extend<char> for Utf16Sink<T, W> {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        let mut buf = [0; 2];
+        for c in iter {
+            if self.error.is_some() {
+                break
+            }
+            for &short in c.encode_utf16(&mut buf).iter() {
+                self.write_short(short);
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::io::{Read, Error, Cursor};
+
+use byteorder::ByteOrder;
+
+use read::{Utf16ReadExt, Shorts, Chars};
+
+/// A reader that knows how many bytes it has left
+pub trait SizedReader: Read {
+    /// Returns how many bytes can still be read
+    fn remaining_bytes(&self) -> usize;
+}
+
+impl<T: AsRef<[u8]>> SizedReader for Cursor<T> {
+    fn remaining_bytes(&self) -> usize {
+        let len = self.get_ref().as_ref().len() as u64;
+        len.saturating_sub(self.position()) as usize
+    }
+}
+
+impl SizedReader for &[u8] {
+    fn remaining_bytes(&self) -> usize {
+        self.len()
+    }
+}
+
+#[derive(Debug)]
+/// Like `Shorts` but with an exact `size_hint` from a `SizedReader`
+pub struct SizedShorts<T: ByteOrder, R>(Shorts<T, R>);
+
+#[derive(Debug)]
+/// Like `Chars` but with a `size_hint` from a `SizedReader`
+pub struct SizedChars<T: ByteOrder, R>(Chars<T, R>);
+
+impl<T: ByteOrder, R: SizedReader> SizedShorts<T, R> {
+    pub(crate) fn new(inner: R) -> Self {
+        SizedShorts(inner.shorts())
+    }
+}
+
+impl<T: ByteOrder, R: SizedReader> SizedChars<T, R> {
+    pub(crate) fn new(inner: R) -> Self {
+        SizedChars(inner.utf16_chars())
+    }
+}
+
+macro_rules! impl_inner {
+    ($($name:ident),*) => {$(
+        impl<T: ByteOrder, R> $name<T, R> {
+            /// Gets a reference to the underlying reader
+            pub fn get_ref(&self) -> &R {
+                self.0.get_ref()
+            }
+            /// Gets a mutable reference to the underlying reader
+            pub fn get_mut(&mut self) -> &mut R {
+                self.0.get_mut()
+            }
+            /// Unwraps this iterator, returning the underlying reader
+            pub fn into_inner(self) -> R {
+                self.0.into_inner()
+            }
+        }
+    )*};
+}
+
+impl_inner!(SizedShorts, SizedChars);
+
+impl<T: ByteOrder, R: SizedReader> Iterator for SizedShorts<T, R> {
+    type Item = Result<u16, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // A trailing odd byte is yielded as an error, so it counts as an item too
+        let n = self.get_ref().remaining_bytes().div_ceil(2);
+        (n, Some(n))
+    }
+}
+
+impl<T: ByteOrder, R: SizedReader> Iterator for SizedChars<T, R> {
+    type Item = Result<char, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Every char takes up 2 or 4 bytes, counting a short that was read ahead
+        let pending = if self.0.pending_short().is_some() { 2 } else { 0 };
+        let n = self.get_ref().remaining_bytes() + pending;
+        (n.div_ceil(4), Some(n.div_ceil(2)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use byteorder::LE;
+    use read::Utf16ReadExt;
+    use test_util::*;
+
+    #[test]
+    fn sized_shorts_hint_is_exact() {
+        let mut bytes = le(&[0x61, 0x62]);
+        bytes.push(0);
+        let mut shorts = Cursor::new(bytes).sized_shorts::<LE>();
+        assert_eq!(shorts.size_hint(), (3, Some(3)));
+        assert_eq!(shorts.next().unwrap().unwrap(), 0x61);
+        assert_eq!(shorts.size_hint(), (2, Some(2)));
+        assert_eq!(shorts.by_ref().count(), 2);
+        assert_eq!(shorts.size_hint(), (0, Some(0)));
+    }
+
+    #[test]
+    fn sized_chars_hint_bounds_the_chars() {
+        let mut chars = Cursor::new(le_str("a\u{1f600}")).sized_chars::<LE>();
+        assert_eq!(chars.size_hint(), (2, Some(3)));
+        assert_eq!(chars.next().unwrap().unwrap(), 'a');
+        assert_eq!(chars.size_hint(), (1, Some(2)));
+        assert_eq!(chars.next().unwrap().unwrap(), '\u{1f600}');
+        assert_eq!(chars.size_hint(), (0, Some(0)));
+        assert!(chars.next().is_none());
+    }
+
+    #[test]
+    fn sized_chars_hint_counts_the_short_after_an_unpaired_surrogate() {
+        let mut chars = Cursor::new(le(&[0xd800, 0x61])).sized_chars::<LE>();
+        assert!(chars.next().unwrap().is_err());
+        assert_eq!(chars.size_hint(), (1, Some(1)));
+        assert_eq!(chars.next().unwrap().unwrap(), 'a');
+        assert_eq!(chars.size_hint(), (0, Some(0)));
+        assert!(chars.next().is_none());
+    }
+}
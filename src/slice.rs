@@ -0,0 +1,305 @@
+use std::char::decode_utf16;
+use std::io::{Error, ErrorKind};
+use std::marker::PhantomData;
+use std::str::{self, EncodeUtf16};
+
+use byteorder::ByteOrder;
+
+use endian::BOM;
+use error::Utf16DecodeError;
+use write::Utf16WriteExt;
+
+/// Decodes utf-16 directly from a byte slice without going through `Read`
+///
+/// The returned iterator yields `Result<char, Error>` like `Utf16ReadExt::utf16_chars`.
+/// An odd trailing byte is yielded as an error with `ErrorKind::UnexpectedEof`.
+pub fn decode_slice<T: ByteOrder>(bytes: &[u8]) -> DecodeSlice<'_, T> {
+    DecodeSlice {
+        bytes,
+        offset: 0,
+        _order: PhantomData,
+    }
+}
+
+/// Decodes utf-16 directly from a byte slice, skipping a leading BOM if `skip_bom` is true
+///
+/// This is `decode_slice` or `from_mapped_bytes` depending on the flag. A BOM that
+/// isn't skipped is yielded as U+FEFF. Unpaired surrogates are yielded as errors
+/// carrying a `Utf16DecodeError`, whose offset is the index of the surrogate in `bytes`
+/// counted in shorts, so it includes a skipped BOM.
+pub fn decode_utf16_slice<T: ByteOrder>(bytes: &[u8], skip_bom: bool) -> DecodeSlice<'_, T> {
+    let mut bom = [0; 2];
+    T::write_u16(&mut bom, BOM);
+    if skip_bom && bytes.starts_with(&bom) {
+        DecodeSlice {
+            bytes: &bytes[2..],
+            offset: 1,
+            _order: PhantomData,
+        }
+    } else {
+        decode_slice(bytes)
+    }
+}
+
+/// Decodes utf-16 from the bytes of a memory mapped file, or any other byte slice
+///
+/// Like `decode_slice` but skips a leading BOM (U+FEFF in endianness `T`).
+/// Use `DecodeSlice::lines` to iterate over the lines instead.
+pub fn from_mapped_bytes<T: ByteOrder>(bytes: &[u8]) -> DecodeSlice<'_, T> {
+    decode_utf16_slice(bytes, true)
+}
+
+/// Decodes a whole byte slice of utf-16 into a `String`
+///
+/// If `skip_bom` is true, a leading BOM is dropped like in `from_mapped_bytes`,
+/// otherwise it's kept as U+FEFF.
+pub fn decode_utf16_string<T: ByteOrder>(bytes: &[u8], skip_bom: bool) -> Result<String, Error> {
+    let mut buf = String::new();
+    decode_utf16_slice::<T>(bytes, skip_bom).read_to_string(&mut buf)?;
+    Ok(buf)
+}
+
+/// Encodes a string as utf-16 bytes in endianness `T`, starting with a BOM if `bom` is true
+///
+/// A BOM isn't added to a string that already starts with one,
+/// see `Utf16WriteExt::write_utf16_with_bom`.
+pub fn encode_utf16_vec<T: ByteOrder>(s: &str, bom: bool) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(2 * s.len() + 2);
+    // Writing to a `Vec` never fails
+    if bom {
+        buf.write_utf16_with_bom::<T>(s).unwrap();
+    } else {
+        buf.write_utf16_string::<T>(s).unwrap();
+    }
+    buf
+}
+
+/// Appends `shorts` to `v` as utf-16 bytes in endianness `T`
+///
+/// This is what `Utf16WriteExt::write_all_shorts` does for a `Vec<u8>`, but the room
+/// for all the bytes is reserved up front and they're encoded in a single pass
+/// instead of going through `Write`.
+pub fn append_utf16_to_vec<T: ByteOrder>(v: &mut Vec<u8>, shorts: &[u16]) {
+    let start = v.len();
+    v.resize(start + 2 * shorts.len(), 0);
+    T::write_u16_into(shorts, &mut v[start..]);
+}
+
+/// Encodes a string as utf-16 bytes in endianness `T` one byte at a time
+///
+/// Like `encode_utf16_vec` without a bom, but nothing is allocated, so the bytes can be
+/// collected into any container or fed to something taking an iterator of bytes.
+pub fn str_to_utf16_bytes<T: ByteOrder>(s: &str) -> Utf16Bytes<'_, T> {
+    Utf16Bytes {
+        units: s.encode_utf16(),
+        next_byte: None,
+        _order: PhantomData,
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An iterator over the utf-16 bytes of a string, made by `str_to_utf16_bytes`
+pub struct Utf16Bytes<'a, T: ByteOrder> {
+    units: EncodeUtf16<'a>,
+    /// The second byte of the last code unit
+    next_byte: Option<u8>,
+    _order: PhantomData<T>,
+}
+
+impl<'a, T: ByteOrder> Iterator for Utf16Bytes<'a, T> {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> {
+        if let Some(b) = self.next_byte.take() {
+            return Some(b)
+        }
+        let mut buf = [0; 2];
+        T::write_u16(&mut buf, self.units.next()?);
+        self.next_byte = Some(buf[1]);
+        Some(buf[0])
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let extra = self.next_byte.is_some() as usize;
+        let (lower, upper) = self.units.size_hint();
+        (2 * lower + extra, upper.map(|upper| 2 * upper + extra))
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An iterator over `char` values of a utf-16 byte slice.
+///
+/// Malformed utf-16 is yielded as an error with `ErrorKind::InvalidData` carrying a
+/// `Utf16DecodeError`. Its offset is the position of the unpaired surrogate in shorts.
+pub struct DecodeSlice<'a, T: ByteOrder> {
+    bytes: &'a [u8],
+    offset: u64,
+    _order: PhantomData<T>,
+}
+
+impl<'a, T: ByteOrder> DecodeSlice<'a, T> {
+    /// Returns the bytes that haven't been decoded yet
+    pub fn as_slice(&self) -> &'a [u8] {
+        self.bytes
+    }
+    /// Decodes the rest of the slice and appends it to `buf`
+    ///
+    /// Returns the number of chars appended. Runs of ASCII are copied into `buf`
+    /// in bulk, which is a lot faster than decoding them one `char` at a time.
+    /// If an error occurs, the buffer is left as it was before the call.
+    pub fn read_to_string(&mut self, buf: &mut String) -> Result<usize, Error> {
+        let start = buf.len();
+        let mut len = 0;
+        buf.reserve(self.bytes.len() / 2);
+        loop {
+            let mut ascii = [0; 128];
+            let mut n = 0;
+            for (a, unit) in ascii.iter_mut().zip(self.bytes.chunks_exact(2)) {
+                let unit = T::read_u16(unit);
+                if unit >= 0x80 {
+                    break
+                }
+                *a = unit as u8;
+                n += 1;
+            }
+            if n > 0 {
+                buf.push_str(str::from_utf8(&ascii[..n]).unwrap());
+                self.bytes = &self.bytes[2 * n..];
+                self.offset += n as u64;
+                len += n;
+                continue
+            }
+            match self.next() {
+                None => break,
+                Some(Ok(c)) => {
+                    buf.push(c);
+                    len += 1;
+                }
+                Some(Err(e)) => {
+                    buf.truncate(start);
+                    return Err(e)
+                }
+            }
+        }
+        Ok(len)
+    }
+    /// Turns this into an iterator over the lines of the rest of the slice
+    ///
+    /// Like `Utf16ReadExt::utf16_lines`, newlines characters aren't included.
+    pub fn lines(self) -> SliceLines<'a, T> {
+        SliceLines(self)
+    }
+}
+
+impl<'a, T: ByteOrder> Iterator for DecodeSlice<'a, T> {
+    type Item = Result<char, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.bytes.len() {
+            0 => None,
+            1 => {
+                self.bytes = &[];
+                let msg = "slice ended in the middle of a short";
+                Some(Err(Error::new(ErrorKind::UnexpectedEof, msg)))
+            }
+            _ => {
+                let units = self.bytes.chunks_exact(2).take(2).map(T::read_u16);
+                let (c, len) = match decode_utf16(units).next().unwrap() {
+                    Ok(c) => (Ok(c), c.len_utf16()),
+                    Err(e) => {
+                        let e = Utf16DecodeError::new(e.unpaired_surrogate(), self.offset);
+                        (Err(e.into()), 1)
+                    }
+                };
+                self.bytes = &self.bytes[2 * len..];
+                self.offset += len as u64;
+                Some(c)
+            }
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let shorts = self.bytes.len().div_ceil(2);
+        (shorts.div_ceil(2), Some(shorts))
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An iterator over the lines of a utf-16 byte slice.
+pub struct SliceLines<'a, T: ByteOrder>(DecodeSlice<'a, T>);
+
+impl<'a, T: ByteOrder> Iterator for SliceLines<'a, T> {
+    type Item = Result<String, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.bytes.is_empty() {
+            return None
+        }
+        let mut buf = String::new();
+        for c in &mut self.0 {
+            match c {
+                Ok('\n') => {
+                    if buf.ends_with('\r') {
+                        buf.pop();
+                    }
+                    break
+                }
+                Ok(c) => buf.push(c),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        Some(Ok(buf))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::LE;
+    use error::Utf16DecodeError;
+    use test_util::*;
+
+    #[test]
+    fn encode_utf16_vec_adds_a_bom_only_if_asked_and_missing() {
+        assert_eq!(encode_utf16_vec::<LE>("a", true), le_str("\u{feff}a"));
+        assert_eq!(encode_utf16_vec::<LE>("a", false), le_str("a"));
+        assert_eq!(encode_utf16_vec::<LE>("\u{feff}a", true), le_str("\u{feff}a"));
+        assert_eq!(encode_utf16_vec::<LE>("a\u{feff}", true), le_str("\u{feff}a\u{feff}"));
+    }
+
+    fn read_to_string(bytes: &[u8]) -> (Result<usize, Error>, String) {
+        let mut buf = "x".to_owned();
+        let r = decode_slice::<LE>(bytes).read_to_string(&mut buf);
+        (r, buf)
+    }
+
+    #[test]
+    fn read_to_string_after_a_full_ascii_run() {
+        // The bulk copy takes at most 128 units at a time
+        let s = "a".repeat(128) + "\u{e9}" + &"b".repeat(130) + "\u{1f600}";
+        let (r, buf) = read_to_string(&le_str(&s));
+        assert_eq!(r.unwrap(), 260);
+        assert_eq!(buf, "x".to_owned() + &s);
+    }
+
+    #[test]
+    fn read_to_string_astral_char_after_ascii() {
+        let (r, buf) = read_to_string(&le_str("ab\u{1f600}c"));
+        assert_eq!(r.unwrap(), 4);
+        assert_eq!(buf, "xab\u{1f600}c");
+    }
+
+    #[test]
+    fn read_to_string_lone_surrogate_after_ascii() {
+        let (r, buf) = read_to_string(&le(&[0x61, 0x62, 0xdc00, 0x63]));
+        let e = r.unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        let e = e.get_ref().unwrap().downcast_ref::<Utf16DecodeError>().unwrap();
+        assert_eq!((e.unpaired_surrogate(), e.offset()), (0xdc00, 2));
+        assert_eq!(buf, "x");
+    }
+
+    #[test]
+    fn read_to_string_odd_trailing_byte() {
+        let mut bytes = le_str("ab");
+        bytes.push(0x63);
+        let (r, buf) = read_to_string(&bytes);
+        assert_eq!(r.unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(buf, "x");
+    }
+}
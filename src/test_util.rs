@@ -0,0 +1,130 @@
+//! Readers, writers and helpers shared by the unit tests
+
+use std::collections::VecDeque;
+use std::io::{Read, Write, Result, Error, ErrorKind};
+
+/// Encodes `units` as little endian bytes
+pub fn le(units: &[u16]) -> Vec<u8> {
+    units.iter().flat_map(|u| u.to_le_bytes().to_vec()).collect()
+}
+
+/// Encodes `s` as little endian utf-16
+pub fn le_str(s: &str) -> Vec<u8> {
+    le(&s.encode_utf16().collect::<Vec<_>>())
+}
+
+/// An error with `ErrorKind::Interrupted`, which should be retried
+pub fn interrupted() -> Error {
+    Error::new(ErrorKind::Interrupted, "interrupted")
+}
+
+/// What a `MockWriter` does once it has taken as many bytes as it can
+#[derive(Debug, Clone, Copy)]
+pub enum Full {
+    /// `write` returns `Ok(0)`
+    Zero,
+    /// `write` fails with `ErrorKind::Other`
+    Error,
+}
+
+#[derive(Debug)]
+/// A writer that takes a limited number of bytes, possibly only a few at a time
+pub struct MockWriter {
+    pub written: Vec<u8>,
+    /// How many times `write` was called, including calls that failed
+    pub calls: usize,
+    capacity: usize,
+    per_call: usize,
+    full: Full,
+    interrupt: bool,
+    interrupted: bool,
+}
+
+impl MockWriter {
+    /// A writer that takes everything
+    pub fn new() -> Self {
+        MockWriter {
+            written: Vec::new(),
+            calls: 0,
+            capacity: usize::MAX,
+            per_call: usize::MAX,
+            full: Full::Error,
+            interrupt: false,
+            interrupted: false,
+        }
+    }
+    /// Takes only `capacity` bytes in total, then does what `full` says
+    pub fn capacity(mut self, capacity: usize, full: Full) -> Self {
+        self.capacity = capacity;
+        self.full = full;
+        self
+    }
+    /// Takes at most `per_call` bytes per call to `write`
+    pub fn per_call(mut self, per_call: usize) -> Self {
+        self.per_call = per_call;
+        self
+    }
+    /// Fails with `Interrupted` before every successful call to `write`
+    pub fn interrupting(mut self) -> Self {
+        self.interrupt = true;
+        self
+    }
+}
+
+impl Write for MockWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.calls += 1;
+        if self.interrupt && !self.interrupted {
+            self.interrupted = true;
+            return Err(interrupted())
+        }
+        self.interrupted = false;
+        if self.written.len() >= self.capacity && !buf.is_empty() {
+            return match self.full {
+                Full::Zero => Ok(0),
+                Full::Error => Err(Error::other("writer is full")),
+            }
+        }
+        let n = buf.len().min(self.per_call).min(self.capacity - self.written.len());
+        self.written.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A reader that returns the given reads one after another, then EOF
+///
+/// A read of bytes that don't fit in the buffer is continued by the next call.
+pub struct ScriptedReader {
+    steps: VecDeque<Result<Vec<u8>>>,
+}
+
+impl ScriptedReader {
+    /// A reader doing `steps` in order
+    pub fn new(steps: Vec<Result<Vec<u8>>>) -> Self {
+        ScriptedReader { steps: steps.into() }
+    }
+    /// A reader returning `bytes` one byte per call
+    pub fn one_byte(bytes: &[u8]) -> Self {
+        ScriptedReader::new(bytes.iter().map(|&b| Ok(vec![b])).collect())
+    }
+}
+
+impl Read for ScriptedReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match self.steps.pop_front() {
+            None => Ok(0),
+            Some(Err(e)) => Err(e),
+            Some(Ok(mut bytes)) => {
+                let n = bytes.len().min(buf.len());
+                buf[..n].copy_from_slice(&bytes[..n]);
+                if n < bytes.len() {
+                    self.steps.push_front(Ok(bytes.split_off(n)));
+                }
+                Ok(n)
+            }
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::io::{Read, Write, Error};
+
+use byteorder::ByteOrder;
+
+use endian::BOM;
+use read::{Utf16ReadExt, Chars};
+use write::{Utf16WriteExt, write_str};
+
+/// Reads utf-16 in endianness `T` from `reader` and writes it as utf-8 to `writer`
+///
+/// The input is streamed one `char` at a time, so it's never read into memory as a whole.
+/// Wrapping `writer` in a `BufWriter` is a good idea, since every `char` is written on its own.
+/// Malformed utf-16 is returned as an error, after everything before it has been written.
+///
+/// Returns the number of bytes written.
+pub fn transcode_to_utf8<T, R, W>(reader: R, writer: &mut W) -> Result<u64, Error>
+where T: ByteOrder, R: Utf16ReadExt, W: Write {
+    let mut written = 0;
+    let mut buf = [0; 4];
+    for c in reader.utf16_chars::<T>() {
+        let bytes = c?.encode_utf8(&mut buf).as_bytes();
+        writer.write_all(bytes)?;
+        written += bytes.len() as u64;
+    }
+    Ok(written)
+}
+
+/// Writes the utf-8 string `s` as utf-16 in endianness `T` to `writer`,
+/// starting with a bom if `bom` is true
+///
+/// Returns the number of bytes written, including the bom.
+pub fn transcode_utf8_to_utf16<T, W>(s: &str, writer: &mut W, bom: bool) -> Result<u64, Error>
+where T: ByteOrder, W: Write {
+    let mut written = 0;
+    if bom {
+        writer.write_bom::<T>()?;
+        written += 2;
+    }
+    write_str::<T, _>(writer, s)?;
+    written += 2 * s.encode_utf16().count() as u64;
+    Ok(written)
+}
+
+/// Copies the shorts of `reader` in endianness `In` to `writer` in endianness `Out`
+///
+/// The shorts aren't decoded, so malformed utf-16 is copied as it is. Since a leading bom
+/// is copied as the value U+FEFF, it ends up matching `Out`. If `rewrite_bom` is false,
+/// a leading bom is instead copied byte for byte, so it still says `In`. A stream ending
+/// on an odd byte is an error with `ErrorKind::UnexpectedEof`.
+///
+/// Returns the number of shorts written.
+pub fn swap_endianness<In, Out, R, W>(mut reader: R, writer: &mut W, rewrite_bom: bool)
+-> Result<u64, Error>
+where In: ByteOrder, Out: ByteOrder, R: Utf16ReadExt, W: Write {
+    let mut buf = [0; 2048];
+    let mut written = 0;
+    loop {
+        let n = reader.read_shorts::<In>(&mut buf)?;
+        if n == 0 {
+            return Ok(written)
+        }
+        let mut shorts = &buf[..n];
+        if written == 0 && !rewrite_bom && shorts[0] == BOM {
+            writer.write_bom::<In>()?;
+            shorts = &shorts[1..];
+        }
+        writer.write_all_shorts::<Out>(shorts)?;
+        written += n as u64;
+    }
+}
+
+#[derive(Debug)]
+/// A reader of utf-8 decoded from a reader of utf-16 in endianness `T`
+///
+/// Made by `Utf16ReadExt::into_utf8_reader`, this lets utf-16 be used with anything
+/// that takes a `Read`, like `std::io::copy`. A `char` whose utf-8 bytes don't all fit in
+/// the buffer given to `read` is kept, and the rest of its bytes are returned by the next
+/// `read`.
+/// Malformed utf-16 is an error with `ErrorKind::InvalidData`. If it comes after some bytes
+/// have been put in the buffer, those bytes are returned first and the error by the next `read`.
+pub struct Utf8Adapter<T: ByteOrder, R> {
+    inner: Chars<T, R>,
+    leftover: [u8; 4],
+    start: usize,
+    end: usize,
+    error: Option<Error>,
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Utf8Adapter<T, R> {
+    /// Makes a new `Utf8Adapter` decoding utf-16 from `inner`
+    pub fn new(inner: R) -> Self {
+        Utf8Adapter {
+            inner: inner.utf16_chars(),
+            leftover: [0; 4],
+            start: 0,
+            end: 0,
+            error: None,
+        }
+    }
+}
+
+impl<T: ByteOrder, R> Utf8Adapter<T, R> {
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+    /// Unwraps this reader, returning the underlying reader
+    ///
+    /// The bytes of a `char` that has only partly been returned by `read` are lost.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Read for Utf8Adapter<T, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut n = ::std::cmp::min(self.end - self.start, buf.len());
+        buf[..n].copy_from_slice(&self.leftover[self.start..self.start + n]);
+        self.start += n;
+        if self.start < self.end {
+            return Ok(n)
+        }
+        if self.error.is_some() {
+            return match n {
+                0 => Err(self.error.take().unwrap()),
+                n => Ok(n),
+            }
+        }
+        while n < buf.len() {
+            let c = match self.inner.next() {
+                Some(Ok(c)) => c,
+                Some(Err(e)) if n == 0 => return Err(e),
+                Some(Err(e)) => {
+                    self.error = Some(e);
+                    break
+                }
+                None => break,
+            };
+            let len = c.len_utf8();
+            if n + len <= buf.len() {
+                c.encode_utf8(&mut buf[n..]);
+                n += len;
+            } else {
+                c.encode_utf8(&mut self.leftover);
+                let fits = buf.len() - n;
+                buf[n..].copy_from_slice(&self.leftover[..fits]);
+                self.start = fits;
+                self.end = len;
+                n += fits;
+            }
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, ErrorKind};
+
+    use byteorder::{LE, BE};
+
+    use super::*;
+    use test_util::*;
+
+    fn be(units: &[u16]) -> Vec<u8> {
+        units.iter().flat_map(|u| u.to_be_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn swap_endianness_rewrites_the_bom_to_match_the_output() {
+        let units = [BOM, 0x61, 0xfeff];
+        let mut out = Vec::new();
+        let r = Cursor::new(le(&units));
+        assert_eq!(swap_endianness::<LE, BE, _, _>(r, &mut out, true).unwrap(), 3);
+        assert_eq!(out, be(&units));
+    }
+
+    #[test]
+    fn swap_endianness_can_keep_the_bom_as_it_was() {
+        let mut out = Vec::new();
+        let r = Cursor::new(le(&[BOM, 0x61, 0xfeff]));
+        assert_eq!(swap_endianness::<LE, BE, _, _>(r, &mut out, false).unwrap(), 3);
+        // Only the leading bom is kept, a later U+FEFF is just a char
+        let mut expected = le(&[BOM]);
+        expected.extend(be(&[0x61, 0xfeff]));
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn swap_endianness_copies_lone_surrogates() {
+        let units = [0xdc00, 0x61, 0xd800];
+        let mut out = Vec::new();
+        let r = Cursor::new(be(&units));
+        assert_eq!(swap_endianness::<BE, LE, _, _>(r, &mut out, false).unwrap(), 3);
+        assert_eq!(out, le(&units));
+    }
+
+    #[test]
+    fn swap_endianness_fails_on_an_odd_byte() {
+        let mut bytes = le(&[0x61]);
+        bytes.push(0);
+        let mut out = Vec::new();
+        let e = swap_endianness::<LE, BE, _, _>(Cursor::new(bytes), &mut out, true).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn transcode_to_utf8_writes_everything_before_malformed_utf16() {
+        let mut out = Vec::new();
+        let r = Cursor::new(le_str("a\u{e9}\u{1f600}"));
+        assert_eq!(transcode_to_utf8::<LE, _, _>(r, &mut out).unwrap(), 7);
+        assert_eq!(out, "a\u{e9}\u{1f600}".as_bytes());
+
+        let mut out = Vec::new();
+        let r = Cursor::new(le(&[0x61, 0xdc00, 0x62]));
+        let e = transcode_to_utf8::<LE, _, _>(r, &mut out).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert_eq!(out, b"a");
+    }
+
+    #[test]
+    fn transcode_utf8_to_utf16_counts_the_bom_and_astral_chars() {
+        let mut out = Vec::new();
+        assert_eq!(transcode_utf8_to_utf16::<BE, _>("a\u{1f600}", &mut out, true).unwrap(), 8);
+        assert_eq!(out, be(&[BOM, 0x61, 0xd83d, 0xde00]));
+
+        let mut out = Vec::new();
+        assert_eq!(transcode_utf8_to_utf16::<LE, _>("a\u{1f600}", &mut out, false).unwrap(), 6);
+        assert_eq!(out, le_str("a\u{1f600}"));
+    }
+
+    #[test]
+    fn utf8_adapter_returns_a_split_char_over_several_reads() {
+        let mut r = Cursor::new(le_str("a\u{1f600}b")).into_utf8_reader::<LE>();
+        let mut buf = [0; 3];
+        assert_eq!(r.read(&mut buf).unwrap(), 3);
+        assert_eq!(buf, [b'a', 0xf0, 0x9f]);
+        assert_eq!(r.read(&mut buf[..1]).unwrap(), 1);
+        assert_eq!(buf[0], 0x98);
+        assert_eq!(r.read(&mut buf).unwrap(), 2);
+        assert_eq!(buf[..2], [0x80, b'b']);
+        assert_eq!(r.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn utf8_adapter_returns_the_bytes_before_an_error_first() {
+        let mut r = Cursor::new(le(&[0x61, 0xd800, 0x62])).into_utf8_reader::<LE>();
+        let mut buf = [0; 8];
+        assert_eq!(r.read(&mut buf).unwrap(), 1);
+        assert_eq!(r.read(&mut buf).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(r.read(&mut buf).unwrap(), 1);
+        assert_eq!(buf[0], b'b');
+    }
+
+    #[test]
+    fn utf8_adapter_works_with_copy() {
+        let mut r = Cursor::new(le_str("x\u{e9}\u{1f600}")).into_utf8_reader::<LE>();
+        let mut out = Vec::new();
+        assert_eq!(::std::io::copy(&mut r, &mut out).unwrap(), 7);
+        assert_eq!(out, "x\u{e9}\u{1f600}".as_bytes());
+    }
+}
@@ -0,0 +1,59 @@
+use std::io::Error;
+use std::marker::PhantomData;
+
+use byteorder::ByteOrder;
+
+use read::{Utf16ReadExt, Shorts, Chars, Lines};
+
+#[derive(Debug)]
+/// A reader with its endianness fixed by the type parameter `T`
+///
+/// Like `AutoEndianReader` but the endianness is known at compile time,
+/// so there's no runtime cost. Made by `Utf16ReadExt::into_utf16_reader`.
+pub struct Utf16Reader<T: ByteOrder, R>(PhantomData<T>, R);
+
+impl<T: ByteOrder, R> Utf16Reader<T, R> {
+    /// Makes a new `Utf16Reader` reading in endianness `T`
+    pub fn new(inner: R) -> Self {
+        Utf16Reader(PhantomData, inner)
+    }
+    /// Gets a reference to the underlying reader
+    pub fn get_ref(&self) -> &R {
+        &self.1
+    }
+    /// Gets a mutable reference to the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.1
+    }
+    /// Unwraps this reader, returning the underlying reader
+    pub fn into_inner(self) -> R {
+        self.1
+    }
+}
+
+impl<T: ByteOrder, R: Utf16ReadExt> Utf16Reader<T, R> {
+    /// Mirror of `Utf16ReadExt::read_u16` without the type parameter for endianness
+    pub fn read_u16(&mut self) -> Result<u16, Error> {
+        self.1.read_u16::<T>()
+    }
+    /// Mirror of `Utf16ReadExt::shorts` without the type parameter for endianness
+    pub fn shorts(self) -> Shorts<T, R> {
+        self.1.shorts()
+    }
+    /// Mirror of `Utf16ReadExt::utf16_chars` without the type parameter for endianness
+    pub fn utf16_chars(self) -> Chars<T, R> {
+        self.1.utf16_chars()
+    }
+    /// Mirror of `Utf16ReadExt::read_utf16_line` without the type parameter for endianness
+    pub fn read_utf16_line(&mut self, buf: &mut String) -> Result<usize, Error> {
+        self.1.read_utf16_line::<T>(buf)
+    }
+    /// Mirror of `Utf16ReadExt::read_utf16_to_string` without the type parameter for endianness
+    pub fn read_utf16_to_string(&mut self, buf: &mut String) -> Result<usize, Error> {
+        self.1.read_utf16_to_string::<T>(buf)
+    }
+    /// Mirror of `Utf16ReadExt::utf16_lines` without the type parameter for endianness
+    pub fn utf16_lines(self) -> Lines<T, R> {
+        self.1.utf16_lines()
+    }
+}
@@ -1,65 +1,488 @@
-use std::io::{Write, Result, Error, ErrorKind};
+use std::char::{decode_utf16, REPLACEMENT_CHARACTER};
+use std::io::{Write, IoSlice, Result, Error, ErrorKind};
+use std::marker::PhantomData;
 
-use byteorder::{ByteOrder, WriteBytesExt};
+use byteorder::{ByteOrder, NativeEndian, WriteBytesExt, LE, BE};
+
+use endian::BOM;
+use error::Utf16DecodeError;
+use pstring::LengthPrefix;
 
 /// An extension of `std::io::Write` for utf16
 pub trait Utf16WriteExt: WriteBytesExt {
     /// Like `Write::write` but with `u16`s
+    ///
+    /// An empty buffer returns `Ok(0)` without writing anything, and that is the only case
+    /// where `Ok(0)` is returned. A writer that doesn't accept any bytes of the first short
+    /// gives an error with `ErrorKind::WriteZero` instead, since every short is written with
+    /// `write_all`, which also retries `ErrorKind::Interrupted`.
+    ///
+    /// **Note:** like `Write::write`, an error after at least one short has been written
+    /// is discarded and `Ok` is returned with the number of shorts written.
+    /// Use `try_write_shorts` to get the error as well, or `write_all_shorts`,
+    /// which never discards errors.
     fn write_shorts<T: ByteOrder>(&mut self, buf: &[u16]) -> Result<usize> {
-        let mut len = 0;
-        for &short in buf {
-            match self.write_u16::<T>(short) {
-                Ok(()) => (),
-                Err(_) if len > 0 => return Ok(len),
-                Err(e) => return Err(e),
+        match self.try_write_shorts::<T>(buf) {
+            Ok(len) => Ok(len),
+            Err((len, _)) if len > 0 => Ok(len),
+            Err((_, e)) => Err(e),
+        }
+    }
+    /// Like `write_shorts` but an error is never discarded
+    ///
+    /// On error, the number of shorts written before it is returned along with the error.
+    fn try_write_shorts<T: ByteOrder>(&mut self, buf: &[u16])
+    -> ::std::result::Result<usize, (usize, Error)> {
+        for (len, &short) in buf.iter().enumerate() {
+            if let Err(e) = self.write_u16::<T>(short) {
+                return Err((len, e))
             }
-            len += 1;
         }
-        Ok(len)
+        Ok(buf.len())
     }
     /// Like `Write::write_all` but with `u16`s
-    fn write_all_shorts<T: ByteOrder>(&mut self, mut buf: &[u16]) -> Result<()> {
-        while !buf.is_empty() {
-            match self.write_shorts::<T>(buf) {
-                Ok(0) => return Err(Error::new(ErrorKind::WriteZero,
-                                               "failed to write whole buffer")),
-                Ok(n) => buf = &buf[n..],
-                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
-                Err(e) => return Err(e),
+    ///
+    /// An empty buffer returns `Ok(())` without writing anything
+    ///
+    /// This doesn't loop over `write_shorts`, so a writer that stops accepting bytes can't
+    /// make it spin. It gives an error with `ErrorKind::WriteZero` like `Write::write_all` does.
+    /// The shorts are written in chunks of `DEFAULT_CHUNK_BYTES` bytes,
+    /// see `write_all_shorts_chunked`.
+    fn write_all_shorts<T: ByteOrder>(&mut self, buf: &[u16]) -> Result<()> {
+        self.write_all_shorts_chunked::<T>(buf, DEFAULT_CHUNK_BYTES)
+    }
+    /// Like `write_all_shorts` but writes at most `chunk_bytes` bytes at a time
+    ///
+    /// If `T` is the native endianness the shorts are written without copying,
+    /// otherwise each chunk is encoded into a buffer of `chunk_bytes` bytes first.
+    /// That buffer is on the stack unless it needs to be bigger than 1 KiB.
+    /// `chunk_bytes` is rounded down to a whole number of shorts, but is at least one short.
+    fn write_all_shorts_chunked<T: ByteOrder>(&mut self, buf: &[u16], chunk_bytes: usize)
+    -> Result<()> {
+        write_chunks::<T, _, _>(self, buf, chunk_bytes, |_| ())
+    }
+    /// Like `write_all_shorts` but `progress` is called after every chunk that was written
+    ///
+    /// `progress` gets the total number of shorts written so far, so the last call gets
+    /// `buf.len()`. Nothing is reported for a chunk that failed to be written.
+    fn write_all_shorts_with<T: ByteOrder, F>(&mut self, buf: &[u16], progress: F) -> Result<()>
+    where F: FnMut(usize) {
+        write_chunks::<T, _, _>(self, buf, DEFAULT_CHUNK_BYTES, progress)
+    }
+    /// Like `write_all_shorts` but all of `buf` is encoded into one buffer
+    /// and written with a single call to `write_all`
+    ///
+    /// This uses as much extra memory as `buf` itself, unless `T` is the native endianness.
+    /// Errors are handled like in `write_all_shorts`.
+    fn write_shorts_buffered<T: ByteOrder>(&mut self, buf: &[u16]) -> Result<()> {
+        self.write_all_shorts_chunked::<T>(buf, 2 * buf.len())
+    }
+    /// Like `write_all_shorts` but `buf` is checked to be well-formed utf-16 first
+    ///
+    /// If `buf` has a surrogate that isn't part of a valid pair, nothing is written
+    /// and `ErrorKind::InvalidData` is returned carrying a `Utf16DecodeError`, whose offset
+    /// is the index of the surrogate in `buf`. Otherwise returns the number of shorts written,
+    /// which is all of them.
+    fn write_shorts_validated<T: ByteOrder>(&mut self, buf: &[u16]) -> Result<usize> {
+        if let Some(i) = find_unpaired_surrogate(buf) {
+            return Err(Utf16DecodeError::new(buf[i], i as u64).into())
+        }
+        self.write_all_shorts::<T>(buf)?;
+        Ok(buf.len())
+    }
+    /// Like `write_all_shorts` but surrogates that aren't part of a valid pair
+    /// are written as U+FFFD (the replacement character)
+    fn write_shorts_lossy<T: ByteOrder>(&mut self, buf: &[u16]) -> Result<()> {
+        if find_unpaired_surrogate(buf).is_none() {
+            return self.write_all_shorts::<T>(buf)
+        }
+        let mut fixed = Vec::with_capacity(buf.len());
+        let mut units = [0; 2];
+        for c in decode_utf16(buf.iter().cloned()) {
+            fixed.extend_from_slice(c.unwrap_or(REPLACEMENT_CHARACTER).encode_utf16(&mut units));
+        }
+        self.write_all_shorts::<T>(&fixed)
+    }
+    /// Like `Write::write_vectored` but with `u16`s, writing all of `bufs`
+    ///
+    /// If `T` is the native endianness, the buffers are written without copying
+    /// with calls to `write_vectored`, otherwise they are written one after
+    /// another with `write_all_shorts`. Either way, this works like `write_all_shorts`:
+    /// it only returns once everything is written or an error happened, which includes
+    /// `ErrorKind::WriteZero` for a writer that stops accepting bytes. How much was written
+    /// before an error isn't reported.
+    ///
+    /// Returns the total number of code units written, which is all of them.
+    fn write_shorts_vectored<T: ByteOrder>(&mut self, bufs: &[&[u16]]) -> Result<usize> {
+        let len = bufs.iter().map(|buf| buf.len()).sum();
+        if is_native::<T>() {
+            for group in bufs.chunks(MAX_IO_SLICES) {
+                let mut slices = [IoSlice::new(&[]); MAX_IO_SLICES];
+                for (slice, buf) in slices.iter_mut().zip(group) {
+                    *slice = IoSlice::new(as_bytes(buf));
+                }
+                let mut slices = &mut slices[..group.len()];
+                // Drops leading empty slices
+                IoSlice::advance_slices(&mut slices, 0);
+                while !slices.is_empty() {
+                    match self.write_vectored(slices) {
+                        Ok(0) => {
+                            let msg = "failed to write whole buffer";
+                            return Err(Error::new(ErrorKind::WriteZero, msg))
+                        }
+                        Ok(n) => IoSlice::advance_slices(&mut slices, n),
+                        Err(ref e) if e.kind() == ErrorKind::Interrupted => (),
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+        } else {
+            for buf in bufs {
+                self.write_all_shorts::<T>(buf)?;
             }
         }
-        Ok(())
+        Ok(len)
     }
     /// Writes a byte order maker character
     fn write_bom<T: ByteOrder>(&mut self) -> Result<()> {
-        self.write_u16::<T>(0xfeff)
+        self.write_u16::<T>(BOM)
+    }
+    /// Writes a byte order maker character if `condition` is true
+    ///
+    /// See `BomAwareWriter` for writing the bom only once something else is written.
+    fn write_bom_if<T: ByteOrder>(&mut self, condition: bool) -> Result<()> {
+        if condition {
+            self.write_bom::<T>()?;
+        }
+        Ok(())
+    }
+    /// Writes a slice of `char`s as UTF-16
+    ///
+    /// Returns the number of chars written. Both code units of a surrogate pair are written
+    /// with a single `write_all`, and a char only counts as written if all of it was.
+    /// Like `write_shorts`, an error after at least one char has been written is discarded.
+    fn write_chars<T: ByteOrder>(&mut self, chars: &[char]) -> Result<usize> {
+        let mut units = [0; 2];
+        let mut bytes = [0; 4];
+        for (len, &c) in chars.iter().enumerate() {
+            let units = c.encode_utf16(&mut units);
+            let bytes = &mut bytes[..2 * units.len()];
+            T::write_u16_into(units, bytes);
+            match self.write_all(bytes) {
+                Ok(()) => (),
+                Err(_) if len > 0 => return Ok(len),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(chars.len())
     }
     /// Writes a string as UTF-16
     ///
-    /// Returns Ok(len) of the string written so far
+    /// Returns Ok(len) of the string written so far.
+    /// An empty string is `FullyComplete` without writing anything.
+    ///
+    /// Each code unit is written as a whole with `write_all`, so a writer only accepting
+    /// one byte at a time doesn't cause a code unit to be cut in half. If writing a code unit
+    /// fails, the encoder in `Missing` starts at that code unit.
     fn write_utf16_string<'a, T: ByteOrder>(&mut self, s: &'a str) -> Result<Utf16Written<'a>> {
-        let mut encoder = s.encode_utf16();
-
-        if let Some(short) = encoder.next() {
-            match self.write_u16::<T>(short) {
-                Ok(()) => (),
+        write_encoder::<T, _>(self, s.encode_utf16())
+    }
+    /// Writes a whole string as UTF-16, failing if any of it couldn't be written
+    ///
+    /// Unlike `write_utf16_string`, a partial write is an error instead of `Missing`.
+    /// Code units are written with `write_all`, so writes failing with `ErrorKind::Interrupted`
+    /// are retried and a writer that stops accepting bytes gives `ErrorKind::WriteZero`.
+    /// How much was written before an error isn't reported, use `write_utf16_string`
+    /// to resume a write.
+    fn write_all_utf16_string<T: ByteOrder>(&mut self, s: &str) -> Result<()> {
+        write_str::<T, _>(self, s)
+    }
+    /// Writes a byte order mark followed by a string as UTF-16
+    ///
+    /// If the string already starts with U+FEFF, that is taken as the bom and no other bom
+    /// is written, so there aren't two. A U+FEFF anywhere else in the string is written as is.
+    ///
+    /// If writing the bom fails, the error is returned without writing any of the string.
+    /// Otherwise this returns what `write_utf16_string` returns for the string.
+    fn write_utf16_with_bom<'a, T: ByteOrder>(&mut self, s: &'a str) -> Result<Utf16Written<'a>> {
+        self.write_bom_if::<T>(!s.starts_with('\u{feff}'))?;
+        self.write_utf16_string::<T>(s)
+    }
+    /// Writes a string as UTF-16 where every `\n` not preceded by `\r` is written as `\r\n`
+    ///
+    /// If writing fails after something has been written, the encoder in `Missing` yields
+    /// exactly the translated code units that weren't written, starting with the one that
+    /// failed, so a write stopping inside a `\r\n` or a surrogate pair can be resumed.
+    fn write_utf16_string_crlf<'a, T: ByteOrder>(&mut self, s: &'a str)
+    -> Result<NewlinesWritten<'a>> {
+        write_newlines::<T, _>(self, EncodeNewlines::new(s, true))
+    }
+    /// Writes a string as UTF-16 where every `\r\n` is written as just `\n`
+    ///
+    /// Partial writes are reported like in `write_utf16_string_crlf`.
+    fn write_utf16_string_lf<'a, T: ByteOrder>(&mut self, s: &'a str)
+    -> Result<NewlinesWritten<'a>> {
+        write_newlines::<T, _>(self, EncodeNewlines::new(s, false))
+    }
+    /// Writes a string as UTF-16 returning the number of code units written
+    ///
+    /// Like `write_shorts`, an error is only returned if nothing could be written,
+    /// otherwise writing stops at the first error and the count so far is returned.
+    /// The count is in code units, so writing can stop in the middle of a surrogate pair,
+    /// which leaves only the high surrogate of that char written.
+    fn write_utf16_string_counted<T: ByteOrder>(&mut self, s: &str) -> Result<usize> {
+        self.write_utf16_from_units::<T, _>(s.encode_utf16())
+    }
+    /// Writes the code units of an iterator, like `write_shorts` but without needing a slice
+    ///
+    /// Returns the number of code units written. Like `write_shorts`, an error is only
+    /// returned if nothing could be written, otherwise writing stops at the first error
+    /// and the count so far is returned. The code unit that failed has already been taken
+    /// from the iterator, so it isn't counted and won't be yielded by it again.
+    fn write_utf16_from_units<T: ByteOrder, I>(&mut self, units: I) -> Result<usize>
+    where I: IntoIterator<Item = u16> {
+        let mut len = 0;
+        for short in units {
+            match write_short::<T, _>(self, short) {
+                Ok(()) => len += 1,
+                Err(_) if len > 0 => return Ok(len),
                 Err(e) => return Err(e),
             }
         }
-        while let Some(short) = encoder.next() {
-            match self.write_u16::<T>(short) {
-                Ok(()) => (),
-                Err(_) => return Ok(Utf16Written::Missing(encoder)),
+        Ok(len)
+    }
+    /// Like `write_utf16_string` but the progress of a partial write doesn't borrow the string
+    ///
+    /// Use `utf16_resume_point` to find where to continue from.
+    fn write_utf16_string_resumable<T: ByteOrder>(&mut self, s: &str) -> Result<Utf16Progress> {
+        let mut written_units = 0;
+        for short in s.encode_utf16() {
+            match write_short::<T, _>(self, short) {
+                Ok(()) => written_units += 1,
+                Err(e) if written_units == 0 => return Err(e),
+                Err(_) => return Ok(Utf16Progress::Missing { written_units }),
             }
         }
-        Ok(Utf16Written::FullyComplete)
+        Ok(Utf16Progress::FullyComplete)
+    }
+    /// Writes a string as its length in code units followed by the string as UTF-16
+    ///
+    /// `L` is the type of the length prefix (`u16` or `u32`), which is written in endianness `T`
+    /// like the string itself. If the string is too long for `L`, nothing is written and
+    /// `ErrorKind::InvalidInput` is returned.
+    fn write_utf16_pstring<L: LengthPrefix, T: ByteOrder>(&mut self, s: &str) -> Result<()> {
+        L::write_len::<T, _>(self, s.encode_utf16().count())?;
+        write_str::<T, _>(self, s)
+    }
+    /// Writes a string as UTF-16 followed by a `\n`
+    fn write_utf16_line<T: ByteOrder>(&mut self, s: &str) -> Result<()> {
+        self.write_utf16_line_with::<T>(s, LineTerminator::Lf)
+    }
+    /// Writes a string as UTF-16 followed by the given line terminator
+    fn write_utf16_line_with<T: ByteOrder>(&mut self, s: &str, terminator: LineTerminator)
+    -> Result<()> {
+        write_str::<T, _>(self, s)?;
+        self.write_all_shorts::<T>(terminator.as_shorts())
+    }
+    /// Writes every string of `lines` as UTF-16, each followed by the given line terminator
+    ///
+    /// An empty iterator writes nothing.
+    fn write_utf16_lines<T, I, S>(&mut self, lines: I, terminator: LineTerminator) -> Result<()>
+    where T: ByteOrder, I: IntoIterator<Item = S>, S: AsRef<str> {
+        for line in lines {
+            self.write_utf16_line_with::<T>(line.as_ref(), terminator)?;
+        }
+        Ok(())
+    }
+    /// Writes a string as UTF-16, replacing chars for which `escape` returns `Some`
+    /// with the returned string
+    fn write_utf16_string_escaped<T: ByteOrder, F>(&mut self, s: &str, escape: F) -> Result<()>
+    where F: Fn(char) -> Option<&'static str> {
+        let mut buf = [0; 2];
+        for c in s.chars() {
+            match escape(c) {
+                Some(replacement) => for short in replacement.encode_utf16() {
+                    self.write_u16::<T>(short)?;
+                },
+                None => self.write_all_shorts::<T>(c.encode_utf16(&mut buf))?,
+            }
+        }
+        Ok(())
+    }
+    /// `write_u16` in the native endianness of the platform
+    fn write_u16_ne(&mut self, n: u16) -> Result<()> {
+        self.write_u16::<NativeEndian>(n)
+    }
+    /// `write_u16` in little endian
+    fn write_u16_le(&mut self, n: u16) -> Result<()> {
+        self.write_u16::<LE>(n)
+    }
+    /// `write_u16` in big endian
+    fn write_u16_be(&mut self, n: u16) -> Result<()> {
+        self.write_u16::<BE>(n)
+    }
+    /// `write_shorts` in the native endianness of the platform
+    fn write_shorts_ne(&mut self, buf: &[u16]) -> Result<usize> {
+        self.write_shorts::<NativeEndian>(buf)
+    }
+    /// `write_all_shorts` in the native endianness of the platform
+    fn write_all_shorts_ne(&mut self, buf: &[u16]) -> Result<()> {
+        self.write_all_shorts::<NativeEndian>(buf)
+    }
+    /// `write_utf16_string` in the native endianness of the platform
+    fn write_utf16_string_ne<'a>(&mut self, s: &'a str) -> Result<Utf16Written<'a>> {
+        self.write_utf16_string::<NativeEndian>(s)
+    }
+    /// `write_bom` in the native endianness of the platform
+    fn write_bom_ne(&mut self) -> Result<()> {
+        self.write_bom::<NativeEndian>()
     }
 }
 
 impl<T: Write> Utf16WriteExt for T {}
 
-use std::str::EncodeUtf16;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+/// The line terminator to write after a line
+pub enum LineTerminator {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl LineTerminator {
+    /// Returns the code units of this line terminator
+    pub fn as_shorts(self) -> &'static [u16] {
+        match self {
+            LineTerminator::Lf => &[0x0a],
+            LineTerminator::CrLf => &[0x0d, 0x0a],
+        }
+    }
+}
+
+/// The most bytes `write_chunks` encodes on the stack instead of allocating a buffer
+const STACK_CHUNK_BYTES: usize = 1024;
+
+/// How many buffers `Utf16WriteExt::write_shorts_vectored` passes to one `write_vectored` at most
+const MAX_IO_SLICES: usize = 64;
+
+/// The chunk size in bytes used by `Utf16WriteExt::write_all_shorts`
+pub const DEFAULT_CHUNK_BYTES: usize = 8 * 1024;
+
+/// Writes `buf` in chunks of at most `chunk_bytes` bytes,
+/// see `Utf16WriteExt::write_all_shorts_chunked`
+fn write_chunks<T, W, F>(w: &mut W, buf: &[u16], chunk_bytes: usize, mut progress: F) -> Result<()>
+where T: ByteOrder, W: Write + ?Sized, F: FnMut(usize) {
+    let chunk_len = ::std::cmp::max(chunk_bytes / 2, 1);
+    let native = is_native::<T>();
+    // Small writes, like a char at a time, are encoded on the stack
+    // so that they don't allocate
+    let mut stack = [0; STACK_CHUNK_BYTES];
+    let mut heap = Vec::new();
+    let needed = 2 * ::std::cmp::min(chunk_len, buf.len());
+    let scratch: &mut [u8] = if native || needed <= STACK_CHUNK_BYTES {
+        &mut stack
+    } else {
+        heap.resize(needed, 0);
+        &mut heap
+    };
+    let mut written = 0;
+    for chunk in buf.chunks(chunk_len) {
+        let bytes = if native {
+            as_bytes(chunk)
+        } else {
+            let bytes = &mut scratch[..2 * chunk.len()];
+            T::write_u16_into(chunk, bytes);
+            bytes
+        };
+        w.write_all(bytes)?;
+        written += chunk.len();
+        progress(written);
+    }
+    Ok(())
+}
+
+/// Writes translated code units, see `Utf16WriteExt::write_utf16_string_crlf`
+fn write_newlines<'a, T: ByteOrder, W: Write + ?Sized>(w: &mut W, encoder: EncodeNewlines<'a>)
+-> Result<NewlinesWritten<'a>> {
+    Ok(match write_units::<T, _, _>(w, encoder)? {
+        None => NewlinesWritten::FullyComplete,
+        Some(rest) => NewlinesWritten::Missing(rest),
+    })
+}
+
+/// Writes the code units of an encoder, see `Utf16WriteExt::write_utf16_string`
+fn write_encoder<'a, T: ByteOrder, W: Write + ?Sized>(w: &mut W, encoder: EncodeUtf16<'a>)
+-> Result<Utf16Written<'a>> {
+    Ok(match write_units::<T, _, _>(w, encoder)? {
+        None => Utf16Written::FullyComplete,
+        Some(rest) => Utf16Written::Missing(rest),
+    })
+}
+
+/// Writes code units until one fails, returning the units starting at that one
+///
+/// An error is only returned if the first code unit couldn't be written.
+fn write_units<T: ByteOrder, W: Write + ?Sized, I>(w: &mut W, mut units: I) -> Result<Option<I>>
+where I: Iterator<Item = u16> + Clone {
+    if let Some(short) = units.next() {
+        write_short::<T, _>(w, short)?;
+    }
+    loop {
+        let rest = units.clone();
+        match units.next() {
+            Some(short) => if write_short::<T, _>(w, short).is_err() {
+                return Ok(Some(rest))
+            },
+            None => return Ok(None),
+        }
+    }
+}
+
+/// Writes a whole string, failing if any code unit couldn't be written
+pub(crate) fn write_str<T: ByteOrder, W: Write + ?Sized>(w: &mut W, s: &str) -> Result<()> {
+    for short in s.encode_utf16() {
+        write_short::<T, _>(w, short)?;
+    }
+    Ok(())
+}
+
+/// Writes a code unit with a single `write_all` of its two bytes
+fn write_short<T: ByteOrder, W: Write + ?Sized>(w: &mut W, short: u16) -> Result<()> {
+    let mut buf = [0; 2];
+    T::write_u16(&mut buf, short);
+    w.write_all(&buf)
+}
+
+/// Returns the index of the first surrogate in `buf` that isn't part of a valid pair
+fn find_unpaired_surrogate(buf: &[u16]) -> Option<usize> {
+    let mut i = 0;
+    while i < buf.len() {
+        match buf[i] {
+            0xd800..=0xdbff => match buf.get(i + 1) {
+                Some(&(0xdc00..=0xdfff)) => i += 2,
+                _ => return Some(i),
+            },
+            0xdc00..=0xdfff => return Some(i),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn is_native<T: ByteOrder>() -> bool {
+    T::read_u16(&1u16.to_ne_bytes()) == 1
+}
+
+fn as_bytes(buf: &[u16]) -> &[u8] {
+    // Safe since `u8` has no alignment requirements and `u16` has no padding
+    unsafe { ::std::slice::from_raw_parts(buf.as_ptr() as *const u8, buf.len() * 2) }
+}
+
+use std::str::{Chars, EncodeUtf16};
 
+#[derive(Debug, Clone)]
 /// Represents how much a string buffer was written
 pub enum Utf16Written<'a> {
     /// Indicates that the whole string buffer written without errors
@@ -67,3 +490,444 @@ pub enum Utf16Written<'a> {
     /// Indicates an erorr occured when writing, also gives the rest of the encoder
     Missing(EncodeUtf16<'a>)
 }
+
+impl<'a> Utf16Written<'a> {
+    /// Returns true if the whole string was written
+    pub fn is_complete(&self) -> bool {
+        matches!(*self, Utf16Written::FullyComplete)
+    }
+    /// Returns true if some of the string is missing
+    pub fn is_missing(&self) -> bool {
+        matches!(*self, Utf16Written::Missing(_))
+    }
+    /// Returns the encoder of the part of the string that's missing, if any
+    pub fn into_remaining(self) -> Option<EncodeUtf16<'a>> {
+        match self {
+            Utf16Written::FullyComplete => None,
+            Utf16Written::Missing(encoder) => Some(encoder),
+        }
+    }
+    /// Returns the part of the string that's missing as a `String`, if any
+    ///
+    /// If the write stopped between the two halves of a surrogate pair, the missing part
+    /// starts with just the low surrogate, which becomes U+FFFD REPLACEMENT CHARACTER.
+    pub fn remaining_as_string(self) -> Option<String> {
+        self.into_remaining().map(|encoder| {
+            let units: Vec<u16> = encoder.collect();
+            String::from_utf16_lossy(&units)
+        })
+    }
+    /// Tries writing the missing part of the string again
+    ///
+    /// This behaves like `Utf16WriteExt::write_utf16_string` on the missing part,
+    /// so an error is returned if none of it could be written.
+    pub fn write_remaining<T, W>(self, w: &mut W) -> Result<Utf16Written<'a>>
+    where T: ByteOrder, W: Utf16WriteExt + ?Sized {
+        match self {
+            Utf16Written::FullyComplete => Ok(Utf16Written::FullyComplete),
+            Utf16Written::Missing(encoder) => write_encoder::<T, _>(w, encoder),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// An iterator over the code units of a string with its newlines translated
+///
+/// This is what's left to write after `Utf16WriteExt::write_utf16_string_crlf`
+/// or `Utf16WriteExt::write_utf16_string_lf` stopped.
+pub struct EncodeNewlines<'a> {
+    chars: Chars<'a>,
+    prev: Option<char>,
+    crlf: bool,
+    /// The low surrogate or `\n` still to be yielded for the last char
+    pending: Option<u16>,
+}
+
+impl<'a> EncodeNewlines<'a> {
+    fn new(s: &'a str, crlf: bool) -> Self {
+        EncodeNewlines {
+            chars: s.chars(),
+            prev: None,
+            crlf,
+            pending: None,
+        }
+    }
+}
+
+impl<'a> Iterator for EncodeNewlines<'a> {
+    type Item = u16;
+    fn next(&mut self) -> Option<u16> {
+        if let Some(short) = self.pending.take() {
+            return Some(short)
+        }
+        let mut c = self.chars.next()?;
+        if !self.crlf && c == '\r' && self.chars.clone().next() == Some('\n') {
+            c = self.chars.next()?;
+        }
+        let prev = self.prev.replace(c);
+        if self.crlf && c == '\n' && prev != Some('\r') {
+            self.pending = Some(0x0a);
+            return Some(0x0d)
+        }
+        let mut buf = [0; 2];
+        let units = c.encode_utf16(&mut buf);
+        self.pending = units.get(1).cloned();
+        Some(units[0])
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Represents how much of a string with translated newlines was written
+pub enum NewlinesWritten<'a> {
+    /// Indicates that the whole string was written without errors
+    FullyComplete,
+    /// Indicates an error occured when writing, also gives the translated code units
+    /// that are missing
+    Missing(EncodeNewlines<'a>),
+}
+
+impl<'a> NewlinesWritten<'a> {
+    /// Returns true if the whole string was written
+    pub fn is_complete(&self) -> bool {
+        matches!(*self, NewlinesWritten::FullyComplete)
+    }
+    /// Returns the encoder of the part of the string that's missing, if any
+    pub fn into_remaining(self) -> Option<EncodeNewlines<'a>> {
+        match self {
+            NewlinesWritten::FullyComplete => None,
+            NewlinesWritten::Missing(encoder) => Some(encoder),
+        }
+    }
+    /// Tries writing the missing part of the string again
+    ///
+    /// An error is returned if none of it could be written.
+    pub fn write_remaining<T, W>(self, w: &mut W) -> Result<NewlinesWritten<'a>>
+    where T: ByteOrder, W: Utf16WriteExt + ?Sized {
+        match self {
+            NewlinesWritten::FullyComplete => Ok(NewlinesWritten::FullyComplete),
+            NewlinesWritten::Missing(encoder) => write_newlines::<T, _>(w, encoder),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A guard that finishes writing a partially written string when it's dropped
+///
+/// Made from the `Utf16Written` of a write, this holds on to the writer and the code units
+/// that are still missing. Dropping it writes them, stopping at the first error, which is
+/// ignored since `Drop` can't return it. Use `complete` to write them right away and get the
+/// error, or `take` to not write them at all.
+pub struct PendingWrite<'a, T: ByteOrder, W: Write + ?Sized + 'a> {
+    writer: &'a mut W,
+    remaining: Option<EncodeUtf16<'a>>,
+    _order: PhantomData<T>,
+}
+
+impl<'a, T: ByteOrder, W: Write + ?Sized> PendingWrite<'a, T, W> {
+    /// Makes a new `PendingWrite` that writes what's missing from `written` to `writer`
+    ///
+    /// If `written` is `FullyComplete`, nothing is pending and dropping this does nothing.
+    pub fn new(writer: &'a mut W, written: Utf16Written<'a>) -> Self {
+        PendingWrite {
+            writer,
+            remaining: written.into_remaining(),
+            _order: PhantomData,
+        }
+    }
+    /// Returns true if nothing is left to be written
+    pub fn is_complete(&self) -> bool {
+        self.remaining.is_none()
+    }
+    /// Defuses this guard, returning the encoder of the part of the string that's missing, if any
+    pub fn take(mut self) -> Option<EncodeUtf16<'a>> {
+        self.remaining.take()
+    }
+    /// Writes the missing part of the string now, returning the first error
+    ///
+    /// Every code unit is written with `write_all`. If one fails, the rest of the string isn't
+    /// written, also not when this is dropped.
+    pub fn complete(mut self) -> Result<()> {
+        self.write_remaining()
+    }
+    fn write_remaining(&mut self) -> Result<()> {
+        if let Some(encoder) = self.remaining.take() {
+            for short in encoder {
+                write_short::<T, _>(self.writer, short)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: ByteOrder, W: Write + ?Sized> Drop for PendingWrite<'a, T, W> {
+    fn drop(&mut self) {
+        let _ = self.write_remaining();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Represents how much of a string was written without borrowing it
+pub enum Utf16Progress {
+    /// Indicates that the whole string was written without errors
+    FullyComplete,
+    /// Indicates an error occured when writing after some code units were written
+    Missing {
+        /// The number of code units that were written
+        written_units: usize,
+    },
+}
+
+/// Finds where to resume writing `s` after `written_units` code units of it were written
+///
+/// Returns the rest of the string, as well as the low surrogate still to be written
+/// if the write stopped in the middle of a surrogate pair.
+pub fn utf16_resume_point(s: &str, written_units: usize) -> (Option<u16>, &str) {
+    let mut units = 0;
+    for (i, c) in s.char_indices() {
+        if units == written_units {
+            return (None, &s[i..])
+        }
+        let mut buf = [0; 2];
+        let encoded = c.encode_utf16(&mut buf);
+        if units + encoded.len() > written_units {
+            return (Some(encoded[1]), &s[i + c.len_utf8()..])
+        }
+        units += encoded.len();
+    }
+    (None, "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::LE;
+    use test_util::*;
+
+    #[test]
+    fn empty_writes_dont_touch_the_writer() {
+        let mut w = MockWriter::new();
+        assert_eq!(w.write_shorts::<LE>(&[]).unwrap(), 0);
+        w.write_all_shorts::<LE>(&[]).unwrap();
+        assert!(w.write_utf16_string::<LE>("").unwrap().is_complete());
+        assert_eq!(w.calls, 0);
+        assert!(w.written.is_empty());
+    }
+
+    #[test]
+    fn write_shorts_reports_a_partial_write() {
+        let mut w = MockWriter::new().capacity(4, Full::Error);
+        assert_eq!(w.write_shorts::<LE>(&[1, 2, 3]).unwrap(), 2);
+        assert_eq!(w.written, le(&[1, 2]));
+    }
+
+    #[test]
+    fn write_shorts_fails_if_nothing_is_written() {
+        let mut w = MockWriter::new().capacity(0, Full::Error);
+        assert_eq!(w.write_shorts::<LE>(&[1, 2]).unwrap_err().kind(), ErrorKind::Other);
+    }
+
+    #[test]
+    fn write_shorts_retries_interrupted_one_byte_writes() {
+        let mut w = MockWriter::new().per_call(1).interrupting();
+        assert_eq!(w.write_shorts::<LE>(&[0x1234, 0xabcd]).unwrap(), 2);
+        assert_eq!(w.written, le(&[0x1234, 0xabcd]));
+    }
+
+    #[test]
+    fn write_all_shorts_fails_on_a_partial_write() {
+        let mut w = MockWriter::new().capacity(4, Full::Error);
+        assert_eq!(w.write_all_shorts::<LE>(&[1, 2, 3]).unwrap_err().kind(), ErrorKind::Other);
+        assert_eq!(w.written, le(&[1, 2]));
+    }
+
+    #[test]
+    fn write_all_shorts_retries_interrupted_one_byte_writes() {
+        let mut w = MockWriter::new().per_call(1).interrupting();
+        w.write_all_shorts::<LE>(&[1, 2, 3]).unwrap();
+        assert_eq!(w.written, le(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn write_utf16_string_gives_the_missing_part() {
+        let mut w = MockWriter::new().capacity(4, Full::Error);
+        let missing = w.write_utf16_string::<LE>("abc").unwrap();
+        assert_eq!(missing.remaining_as_string().unwrap(), "c");
+        assert_eq!(w.written, le_str("ab"));
+    }
+
+    #[test]
+    fn write_utf16_string_stops_at_a_stalled_writer() {
+        let mut w = MockWriter::new().capacity(2, Full::Zero);
+        let missing = w.write_utf16_string::<LE>("abc").unwrap();
+        assert_eq!(missing.remaining_as_string().unwrap(), "bc");
+        assert_eq!(w.written, le_str("a"));
+    }
+
+    #[test]
+    fn write_utf16_string_fails_if_nothing_is_written() {
+        let mut w = MockWriter::new().capacity(0, Full::Error);
+        assert!(w.write_utf16_string::<LE>("abc").is_err());
+    }
+
+    #[test]
+    fn write_utf16_string_retries_interrupted_one_byte_writes() {
+        let mut w = MockWriter::new().per_call(1).interrupting();
+        assert!(w.write_utf16_string::<LE>("a\u{1f600}").unwrap().is_complete());
+        assert_eq!(w.written, le_str("a\u{1f600}"));
+    }
+
+    /// Writes "a😀b" into a writer taking `capacity` bytes, then the rest into another one
+    fn write_and_resume(capacity: usize) -> (Vec<u8>, Vec<u8>) {
+        let mut first = MockWriter::new().capacity(capacity, Full::Error);
+        let missing = first.write_utf16_string::<LE>("a\u{1f600}b").unwrap();
+        assert!(missing.is_missing());
+        let mut rest = MockWriter::new();
+        assert!(missing.write_remaining::<LE, _>(&mut rest).unwrap().is_complete());
+        (first.written, rest.written)
+    }
+
+    #[test]
+    fn resume_between_the_halves_of_a_surrogate_pair() {
+        let (first, rest) = write_and_resume(4);
+        assert_eq!(first, le(&[0x61, 0xd83d]));
+        assert_eq!(rest, le(&[0xde00, 0x62]));
+    }
+
+    #[test]
+    fn resume_after_a_surrogate_pair() {
+        let (first, rest) = write_and_resume(6);
+        assert_eq!(first, le(&[0x61, 0xd83d, 0xde00]));
+        assert_eq!(rest, le(&[0x62]));
+    }
+
+    #[test]
+    fn missing_part_starting_with_a_low_surrogate_is_replaced() {
+        let mut w = MockWriter::new().capacity(4, Full::Error);
+        let missing = w.write_utf16_string::<LE>("a\u{1f600}b").unwrap();
+        assert_eq!(missing.remaining_as_string().unwrap(), "\u{fffd}b");
+    }
+
+    /// Writes `s` with translated newlines into a writer taking `capacity` bytes,
+    /// then the rest into the same writer with no limit, returning everything written
+    fn write_newlines_and_resume(s: &str, crlf: bool, capacity: usize) -> Vec<u8> {
+        let mut first = MockWriter::new().capacity(capacity, Full::Error);
+        let missing = if crlf {
+            first.write_utf16_string_crlf::<LE>(s)
+        } else {
+            first.write_utf16_string_lf::<LE>(s)
+        }.unwrap();
+        assert!(!missing.is_complete());
+        let mut rest = MockWriter::new();
+        assert!(missing.write_remaining::<LE, _>(&mut rest).unwrap().is_complete());
+        first.written.extend(rest.written);
+        first.written
+    }
+
+    #[test]
+    fn resumed_crlf_write_translates_every_newline() {
+        assert_eq!(write_newlines_and_resume("a\nb\nc", true, 8), le_str("a\r\nb\r\nc"));
+    }
+
+    #[test]
+    fn resume_crlf_write_between_cr_and_lf() {
+        for capacity in (2..14).step_by(2) {
+            assert_eq!(write_newlines_and_resume("a\nb\r\n\u{1f600}", true, capacity),
+                       le_str("a\r\nb\r\n\u{1f600}"));
+        }
+    }
+
+    #[test]
+    fn resume_lf_write_in_the_middle() {
+        for capacity in (2..10).step_by(2) {
+            assert_eq!(write_newlines_and_resume("a\r\n\u{1f600}\r\nb", false, capacity),
+                       le_str("a\n\u{1f600}\nb"));
+        }
+    }
+
+    #[test]
+    fn native_endian_methods_match_the_generic_ones() {
+        let (mut ne, mut generic) = (Vec::new(), Vec::new());
+        ne.write_u16_ne(0x1234).unwrap();
+        generic.write_u16::<NativeEndian>(0x1234).unwrap();
+        ne.write_shorts_ne(&[1, 2]).unwrap();
+        generic.write_shorts::<NativeEndian>(&[1, 2]).unwrap();
+        ne.write_all_shorts_ne(&[3, 4]).unwrap();
+        generic.write_all_shorts::<NativeEndian>(&[3, 4]).unwrap();
+        ne.write_utf16_string_ne("a\u{1f600}").unwrap();
+        generic.write_utf16_string::<NativeEndian>("a\u{1f600}").unwrap();
+        ne.write_bom_ne().unwrap();
+        generic.write_bom::<NativeEndian>().unwrap();
+        assert_eq!(ne, generic);
+        assert_eq!(&ne[..2], &0x1234u16.to_ne_bytes());
+    }
+
+    #[test]
+    fn empty_writes_succeed_on_a_full_writer() {
+        let mut w = MockWriter::new().capacity(0, Full::Error);
+        assert_eq!(w.write_shorts::<LE>(&[]).unwrap(), 0);
+        w.write_all_shorts::<LE>(&[]).unwrap();
+        assert_eq!(w.calls, 0);
+    }
+
+    #[test]
+    fn writer_failing_on_the_first_short() {
+        let mut w = MockWriter::new().capacity(0, Full::Error);
+        assert_eq!(w.write_all_shorts::<LE>(&[1, 2]).unwrap_err().kind(), ErrorKind::Other);
+        let mut w = MockWriter::new().capacity(0, Full::Zero);
+        assert_eq!(w.write_shorts::<LE>(&[1, 2]).unwrap_err().kind(), ErrorKind::WriteZero);
+        assert_eq!(w.write_all_shorts::<LE>(&[1, 2]).unwrap_err().kind(), ErrorKind::WriteZero);
+        assert!(w.written.is_empty());
+    }
+
+    #[test]
+    fn writer_accepting_some_bytes_then_zero() {
+        let mut w = MockWriter::new().capacity(3, Full::Zero).per_call(1);
+        assert_eq!(w.write_shorts::<LE>(&[1, 2, 3]).unwrap(), 1);
+        assert_eq!(w.calls, 4);
+        let mut w = MockWriter::new().capacity(3, Full::Zero).per_call(1);
+        assert_eq!(w.write_all_shorts::<LE>(&[1, 2, 3]).unwrap_err().kind(), ErrorKind::WriteZero);
+        assert_eq!(w.written, [1, 0, 2]);
+        assert_eq!(w.calls, 4);
+    }
+
+    #[test]
+    fn write_u16_le_and_be_byte_order() {
+        let mut w = Vec::new();
+        w.write_u16_le(0x1234).unwrap();
+        w.write_u16_be(0x1234).unwrap();
+        assert_eq!(w, [0x34, 0x12, 0x12, 0x34]);
+    }
+
+    #[test]
+    fn write_utf16_with_bom_writes_one_bom() {
+        let mut w = Vec::new();
+        assert!(w.write_utf16_with_bom::<LE>("a\u{feff}").unwrap().is_complete());
+        assert_eq!(w, le_str("\u{feff}a\u{feff}"));
+        let mut w = Vec::new();
+        w.write_utf16_with_bom::<LE>("\u{feff}a").unwrap();
+        assert_eq!(w, le_str("\u{feff}a"));
+    }
+
+    #[test]
+    fn write_chars_writes_astral_chars_whole() {
+        let mut w = Vec::new();
+        assert_eq!(w.write_chars::<LE>(&['a', '\u{1f600}', 'b']).unwrap(), 3);
+        assert_eq!(w, le_str("a\u{1f600}b"));
+        // Only the high surrogate of the second char fits
+        let mut w = MockWriter::new().capacity(4, Full::Zero);
+        assert_eq!(w.write_chars::<LE>(&['a', '\u{1f600}', 'b']).unwrap(), 1);
+        assert_eq!(w.written, le(&[0x61, 0xd83d]));
+    }
+
+    #[test]
+    fn write_utf16_from_units_counts_whole_units() {
+        let mut w = MockWriter::new().capacity(5, Full::Zero);
+        let mut units = "abcd".encode_utf16();
+        assert_eq!(w.write_utf16_from_units::<LE, _>(units.by_ref()).unwrap(), 2);
+        assert_eq!(w.written, le_str("abc")[..5]);
+        // The unit that failed was taken from the iterator
+        assert_eq!(units.collect::<Vec<_>>(), [0x64]);
+        let e = w.write_utf16_from_units::<LE, _>("e".encode_utf16()).unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::WriteZero);
+    }
+}